@@ -7,7 +7,7 @@ use cosmic::{
 	iced::{
 		Subscription,
 		event::{Event, Status, listen_with},
-		window::Id,
+		window::{self, Id},
 	},
 	iced_core::keyboard,
 	iced_widget::column,
@@ -22,7 +22,10 @@ use message::{MenuActions, Message, task};
 use state::State;
 use tracing::error;
 
-use crate::{trans, utils::cfg::script::ScriptCfg};
+use crate::{
+	trans,
+	utils::cfg::script::{ScriptCfg, font},
+};
 
 pub mod dialog;
 pub mod message;
@@ -83,7 +86,11 @@ impl Application for AstroMark {
 			panic!()
 		};
 
-		let tasks = [app.set_window_title(trans!("astromark"), id)];
+		let tasks = [
+			app.set_window_title(trans!("astromark"), id),
+			font::load(),
+			state::editor::history::prune(),
+		];
 
 		(app, Task::batch(tasks))
 	}
@@ -101,6 +108,10 @@ impl Application for AstroMark {
 			#[rustfmt::skip]
 			Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
 				if status != Status::Captured => Some(Message::KeyPress(key, modifiers)),
+			Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+				Some(Message::ModifiersChanged(modifiers))
+			}
+			Event::Window(window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
 
 			_ => None,
 		})];
@@ -130,6 +141,20 @@ impl Application for AstroMark {
 	}
 
 	fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
+		// Sent to the tab of the editor, rather than whichever tab is active
+		if let Message::ToEditor(id, message) = message {
+			let tab = self
+				.tabs
+				.iter()
+				.find(|(_, state)| state.editor_id() == Some(id))
+				.map(|(tab, _)| *tab);
+			return match tab {
+				Some(tab) => self.update_state(tab, *message),
+				// The tab was closed in the meantime
+				None => Task::none(),
+			};
+		}
+
 		self.update_tabs(&message);
 
 		macro_rules! return_if_some {
@@ -145,15 +170,25 @@ impl Application for AstroMark {
 			self.keybinds(&message);
 		];
 
-		if let Some(state) = self.tabs.get_mut(&self.model.active()) {
-			state.update(&mut self.cfg, message.clone())
-		} else {
-			Task::none()
-		}
+		self.update_state(self.model.active(), message)
 	}
 }
 
 impl AstroMark {
+	/// Updates the [State] of a tab
+	fn update_state(&mut self, tab: Entity, message: Message) -> Task<Message> {
+		let Some(state) = self.tabs.get_mut(&tab) else {
+			return Task::none();
+		};
+
+		let task = state.update(&mut self.cfg, message);
+		// The name of a document can change with its text, such as the title of its front matter
+		if state.take_renamed() {
+			self.model.text_set(tab, state.to_string());
+		}
+		task
+	}
+
 	fn add_tab(&mut self, state: State) {
 		let tab = self.model.insert().text(state.to_string()).closable().id();
 
@@ -215,6 +250,7 @@ impl AstroMark {
 			file_menu.append(&mut vec![
 				Item::Button(trans!("save"), None, MenuActions::Save),
 				Item::Button(trans!("save_as"), None, MenuActions::SaveAs),
+				Item::Button(trans!("export_html"), None, MenuActions::ExportHtml),
 				Item::Divider,
 			]);
 		}
@@ -226,12 +262,121 @@ impl AstroMark {
 			Item::Button(trans!("go_home"), None, MenuActions::GoHome),
 		]);
 
-		menu::bar(vec![menu::Tree::with_children(
+		let view_menu = vec![
+			Item::Button(trans!("zoom_in"), None, MenuActions::ZoomIn),
+			Item::Button(trans!("zoom_out"), None, MenuActions::ZoomOut),
+			Item::Button(trans!("zoom_reset"), None, MenuActions::ZoomReset),
+			Item::Divider,
+			Item::Button(trans!("zoom_in_editor"), None, MenuActions::ZoomInEditor),
+			Item::Button(trans!("zoom_out_editor"), None, MenuActions::ZoomOutEditor),
+			Item::Button(trans!("zoom_in_preview"), None, MenuActions::ZoomInPreview),
+			Item::Button(
+				trans!("zoom_out_preview"),
+				None,
+				MenuActions::ZoomOutPreview,
+			),
+			Item::Divider,
+			Item::Button(trans!("problems"), None, MenuActions::Problems),
+			Item::Button(trans!("metadata"), None, MenuActions::Metadata),
+			Item::Button(trans!("backlinks"), None, MenuActions::Backlinks),
+			Item::Divider,
+			Item::Button(trans!("check_links"), None, MenuActions::CheckLinks),
+			Item::Button(
+				trans!("check_workspace_links"),
+				None,
+				MenuActions::CheckWorkspaceLinks,
+			),
+		];
+
+		let keybinds = self.cfg.flags.general_keybinds();
+
+		let mut menus = vec![menu::Tree::with_children(
 			menu::root(trans!("file")),
-			menu::items(&self.cfg.flags.general_keybinds(), file_menu),
-		)])
-		.item_height(ItemHeight::Dynamic(40))
-		.into()
+			menu::items(&keybinds, file_menu),
+		)];
+		if let State::Editor(_) = self.current_state() {
+			let edit_menu = vec![
+				Item::Button(trans!("undo"), None, MenuActions::Undo),
+				Item::Button(trans!("redo"), None, MenuActions::Redo),
+				Item::Divider,
+				Item::Button(
+					trans!("add_cursor_above"),
+					None,
+					MenuActions::AddCursorAbove,
+				),
+				Item::Button(
+					trans!("add_cursor_below"),
+					None,
+					MenuActions::AddCursorBelow,
+				),
+				Item::Button(
+					trans!("add_next_occurrence"),
+					None,
+					MenuActions::AddNextOccurrence,
+				),
+				Item::Button(
+					trans!("select_all_occurrences"),
+					None,
+					MenuActions::SelectAllOccurrences,
+				),
+				Item::Divider,
+				Item::Button(trans!("reflow"), None, MenuActions::Reflow),
+				Item::Button(trans!("surround"), None, MenuActions::Surround),
+			];
+
+			menus.push(menu::Tree::with_children(
+				menu::root(trans!("edit")),
+				menu::items(&keybinds, edit_menu),
+			));
+
+			let format_menu = vec![
+				Item::Button(trans!("format_document"), None, MenuActions::FormatDocument),
+				Item::Divider,
+				Item::Button(trans!("bold"), None, MenuActions::Bold),
+				Item::Button(trans!("italic"), None, MenuActions::Italic),
+				Item::Button(trans!("strikethrough"), None, MenuActions::Strikethrough),
+				Item::Button(trans!("inline_code"), None, MenuActions::InlineCode),
+				Item::Button(trans!("code_block"), None, MenuActions::CodeBlock),
+				Item::Button(trans!("link"), None, MenuActions::Link),
+				Item::Divider,
+				Item::Button(trans!("heading_up"), None, MenuActions::HeadingUp),
+				Item::Button(trans!("heading_down"), None, MenuActions::HeadingDown),
+				Item::Divider,
+				Item::Button(trans!("table_align"), None, MenuActions::TableAlign),
+				Item::Button(
+					trans!("table_insert_row"),
+					None,
+					MenuActions::TableInsertRow,
+				),
+				Item::Button(
+					trans!("table_delete_row"),
+					None,
+					MenuActions::TableDeleteRow,
+				),
+				Item::Button(
+					trans!("table_insert_column"),
+					None,
+					MenuActions::TableInsertColumn,
+				),
+				Item::Button(
+					trans!("table_delete_column"),
+					None,
+					MenuActions::TableDeleteColumn,
+				),
+				Item::Button(trans!("table_sort"), None, MenuActions::TableSort),
+			];
+
+			menus.push(menu::Tree::with_children(
+				menu::root(trans!("format")),
+				menu::items(&keybinds, format_menu),
+			));
+		}
+		menus.push(menu::Tree::with_children(
+			menu::root(trans!("view")),
+			menu::items(&keybinds, view_menu),
+		));
+
+		menu::bar(menus).item_height(ItemHeight::Dynamic(40)).into()
 	}
 
 	fn keybinds(&self, message: &Message) -> Option<Task<Message>> {
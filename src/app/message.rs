@@ -1,20 +1,86 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc, time::SystemTime};
 
 use cosmic::{
 	Action,
 	app::Task,
 	iced::keyboard::{Key, Modifiers},
+	iced_widget::scrollable::Viewport,
 	widget::{markdown, menu::action::MenuAction, segmented_button::Entity, text_editor},
 };
 use cosmic_files::dialog::{DialogMessage, DialogResult};
 
-use crate::create_rhai_mod;
+use crate::{
+	app::state::editor::{attach::Png, cursors, preview, prompt::Prompt},
+	create_rhai_mod,
+	md::{format::Style, lint::Diagnostic, spell::Misspelling, stats::Stats, table::Command},
+	utils::{cfg::zoom::Pane, links::BrokenLink},
+};
 
 #[derive(Debug, Clone)]
 pub enum Message {
+	/// A message for the editor with the id, which may not be in the active tab anymore, such as
+	/// the result of work done in the background
+	ToEditor(u64, Box<Message>),
 	Edit(text_editor::Action),
-	Parsed(Vec<markdown::Item>),
+	Cursors(cursors::Command),
+	/// Tracked by the editor, since the actions of the editor widget do not include them
+	ModifiersChanged(Modifiers),
+	EditorScrolled(Viewport),
+	Undo,
+	Redo,
+	Reflow,
+	/// Normalizes the style of the whole document
+	FormatDocument,
+	/// Surrounds the selection with the opening and closing text
+	Surround(String, String),
+	ToggleStyle(Style),
+	ToggleCodeBlock,
+	/// Removes the link the cursor is in, or asks for the URL of a new one
+	ToggleLink,
+	/// Changes the heading level of the selected lines by the amount
+	ShiftHeading(isize),
+	Table(Command),
+	/// Reads the clipboard, so that pasted values can be turned into a table
+	Paste,
+	Pasted(Option<String>),
+	PastedImage(Option<Png>),
+	/// A file was dragged and dropped onto the window
+	FileDropped(PathBuf),
+	Parsed(Vec<preview::Item>),
+	ImageLoaded(PathBuf, Option<SystemTime>, preview::image::Image),
+	DiagramRendered(u64, preview::diagram::Diagram),
+	/// Analyzes the text, unless it has changed again since the numbered change
+	Analyze(u64, Arc<str>),
+	Counted(Stats),
+	Linted(Vec<Diagnostic>),
+	/// Shows or hides the problems found by the linter
+	ToggleProblems,
+	/// Checks the links of the document, or of every note in the workspace
+	CheckLinks(bool),
+	LinksChecked(Vec<BrokenLink>),
+	/// Shows or hides the fields of the front matter
+	ToggleMetadata,
+	/// Shows or hides the notes linking to the document
+	ToggleBacklinks,
+	BacklinksFound(Vec<PathBuf>),
+	/// The notes of the workspace, which wiki links are completed from
+	NotesListed(Vec<PathBuf>),
+	/// Replaces the name of the note being typed in a wiki link
+	CompleteNote(String),
+	/// Moves the cursor to the byte column of a line
+	GoTo(usize, usize),
+	/// Checks or unchecks the task list item whose checkbox starts at the byte offset
+	ToggleTask(usize),
+	SpellChecked(Vec<Misspelling>),
+	/// The words the misspelled one may have been meant as
+	Suggested(Misspelling, Vec<String>),
+	/// Replaces the misspelled word the cursor is in
+	ReplaceWord(String),
+	/// Adds the misspelled word the cursor is in to the personal dictionary
+	AddToDictionary,
 	Url(markdown::Url),
+	/// Moves the cursor to the heading with the anchor, scrolling the preview to it
+	ScrollToAnchor(String),
 	Save,
 
 	KeyPress(Key, Modifiers),
@@ -23,15 +89,32 @@ pub enum Message {
 	SaveAsFilePicker,
 	SaveAsFileResult(DialogResult),
 	SaveAs(PathBuf),
+	/// Writes the document as an HTML page next to it
+	ExportHtml,
 
 	OpenFilePicker,
 	OpenFileResult(DialogResult),
 
 	OpenEditor(Option<PathBuf>),
+	/// Opens a linked document, scrolling to the heading with the anchor if there is one
+	OpenLink(PathBuf, Option<String>),
+	/// Opens the note a wiki link points to by name, creating it if it does not exist
+	OpenNote(String, Option<String>),
+	/// Opens a document, moving the cursor to the byte column of a line
+	OpenAt(PathBuf, usize, usize),
 	OpenHome,
 	NewTab,
 	SwitchToTab(Entity),
 	KillTab(Entity),
+
+	OpenPrompt(Prompt),
+	PromptInput(String),
+	PromptSubmit,
+	PromptCancel,
+
+	ZoomIn(Pane),
+	ZoomOut(Pane),
+	ZoomReset,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -42,6 +125,42 @@ pub enum MenuActions {
 	NewFile,
 	NewTab,
 	GoHome,
+	ExportHtml,
+	Undo,
+	Redo,
+	AddCursorAbove,
+	AddCursorBelow,
+	AddNextOccurrence,
+	SelectAllOccurrences,
+	Reflow,
+	Surround,
+	FormatDocument,
+	Bold,
+	Italic,
+	Strikethrough,
+	InlineCode,
+	CodeBlock,
+	Link,
+	HeadingUp,
+	HeadingDown,
+	TableAlign,
+	TableInsertRow,
+	TableDeleteRow,
+	TableInsertColumn,
+	TableDeleteColumn,
+	TableSort,
+	ZoomIn,
+	ZoomOut,
+	ZoomReset,
+	ZoomInEditor,
+	ZoomOutEditor,
+	ZoomInPreview,
+	ZoomOutPreview,
+	Problems,
+	Metadata,
+	Backlinks,
+	CheckLinks,
+	CheckWorkspaceLinks,
 }
 
 impl From<MenuActions> for Message {
@@ -53,6 +172,44 @@ impl From<MenuActions> for Message {
 			MenuActions::NewFile => Self::OpenEditor(None),
 			MenuActions::NewTab => Self::NewTab,
 			MenuActions::GoHome => Self::OpenHome,
+			MenuActions::ExportHtml => Self::ExportHtml,
+			MenuActions::Undo => Self::Undo,
+			MenuActions::Redo => Self::Redo,
+			MenuActions::AddCursorAbove => Self::Cursors(cursors::Command::AddAbove),
+			MenuActions::AddCursorBelow => Self::Cursors(cursors::Command::AddBelow),
+			MenuActions::AddNextOccurrence => Self::Cursors(cursors::Command::AddNextOccurrence),
+			MenuActions::SelectAllOccurrences => {
+				Self::Cursors(cursors::Command::SelectAllOccurrences)
+			}
+			MenuActions::Reflow => Self::Reflow,
+			MenuActions::Surround => Self::OpenPrompt(Prompt::Surround),
+			MenuActions::FormatDocument => Self::FormatDocument,
+			MenuActions::Bold => Self::ToggleStyle(Style::Bold),
+			MenuActions::Italic => Self::ToggleStyle(Style::Italic),
+			MenuActions::Strikethrough => Self::ToggleStyle(Style::Strikethrough),
+			MenuActions::InlineCode => Self::ToggleStyle(Style::Code),
+			MenuActions::CodeBlock => Self::ToggleCodeBlock,
+			MenuActions::Link => Self::ToggleLink,
+			MenuActions::HeadingUp => Self::ShiftHeading(1),
+			MenuActions::HeadingDown => Self::ShiftHeading(-1),
+			MenuActions::TableAlign => Self::Table(Command::Align),
+			MenuActions::TableInsertRow => Self::Table(Command::InsertRow),
+			MenuActions::TableDeleteRow => Self::Table(Command::DeleteRow),
+			MenuActions::TableInsertColumn => Self::Table(Command::InsertColumn),
+			MenuActions::TableDeleteColumn => Self::Table(Command::DeleteColumn),
+			MenuActions::TableSort => Self::Table(Command::Sort),
+			MenuActions::ZoomIn => Self::ZoomIn(Pane::Both),
+			MenuActions::ZoomOut => Self::ZoomOut(Pane::Both),
+			MenuActions::ZoomReset => Self::ZoomReset,
+			MenuActions::ZoomInEditor => Self::ZoomIn(Pane::Editor),
+			MenuActions::ZoomOutEditor => Self::ZoomOut(Pane::Editor),
+			MenuActions::ZoomInPreview => Self::ZoomIn(Pane::Preview),
+			MenuActions::ZoomOutPreview => Self::ZoomOut(Pane::Preview),
+			MenuActions::Problems => Self::ToggleProblems,
+			MenuActions::Metadata => Self::ToggleMetadata,
+			MenuActions::Backlinks => Self::ToggleBacklinks,
+			MenuActions::CheckLinks => Self::CheckLinks(false),
+			MenuActions::CheckWorkspaceLinks => Self::CheckLinks(true),
 		}
 	}
 }
@@ -77,5 +234,41 @@ create_rhai_mod! {
 		NewFile;
 		NewTab;
 		GoHome;
+		ExportHtml;
+		Undo;
+		Redo;
+		AddCursorAbove;
+		AddCursorBelow;
+		AddNextOccurrence;
+		SelectAllOccurrences;
+		Reflow;
+		Surround;
+		FormatDocument;
+		Bold;
+		Italic;
+		Strikethrough;
+		InlineCode;
+		CodeBlock;
+		Link;
+		HeadingUp;
+		HeadingDown;
+		TableAlign;
+		TableInsertRow;
+		TableDeleteRow;
+		TableInsertColumn;
+		TableDeleteColumn;
+		TableSort;
+		ZoomIn;
+		ZoomOut;
+		ZoomReset;
+		ZoomInEditor;
+		ZoomOutEditor;
+		ZoomInPreview;
+		ZoomOutPreview;
+		Problems;
+		Metadata;
+		Backlinks;
+		CheckLinks;
+		CheckWorkspaceLinks;
 	]
 }
@@ -28,6 +28,22 @@ impl State {
 		Self::Home(home::Home::new())
 	}
 
+	/// The id of the editor, if the [State] is one
+	pub fn editor_id(&self) -> Option<u64> {
+		match self {
+			Self::Editor(editor) => Some(editor.id()),
+			Self::Home(_) => None,
+		}
+	}
+
+	/// Whether the name of the [State] may have changed since this was last called
+	pub fn take_renamed(&mut self) -> bool {
+		match self {
+			Self::Editor(editor) => editor.take_renamed(),
+			Self::Home(_) => false,
+		}
+	}
+
 	/// Checks if the current [State] should be overwritten by a new one
 	pub fn can_overwrite(&self, message: &Message) -> bool {
 		if matches!(message, Message::NewTab) {
@@ -47,6 +63,9 @@ impl State {
 	pub fn from_message(flags: &Flags, message: &Message) -> Option<Self> {
 		match message {
 			Message::OpenEditor(path) => Some(Self::editor(flags, path)),
+			Message::OpenLink(path, _) | Message::OpenAt(path, ..) => {
+				Some(Self::editor(flags, &Some(path.clone())))
+			}
 			Message::OpenHome | Message::NewTab => Some(Self::home()),
 
 			_ => None,
@@ -66,7 +85,7 @@ impl State {
 			recent.write();
 		}
 
-		Self::Editor(editor::Editor::new(path.clone()))
+		Self::Editor(editor::Editor::new(path.clone(), flags))
 	}
 }
 
@@ -1,17 +1,26 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+	collections::{BTreeMap, HashSet},
+	ops::Range,
+	path::PathBuf,
+	sync::{
+		Arc,
+		atomic::{AtomicU64, Ordering},
+	},
+	time::Duration,
+};
 
 use cosmic::{
 	Element,
 	app::Task,
-	iced::{
-		Font, Length,
-		keyboard::{self, key::Named},
+	iced::{Length, clipboard, highlighter::Theme, keyboard::Modifiers, widget::text::Wrapping},
+	iced_widget::{
+		column, row,
+		scrollable::{self, AbsoluteOffset, RelativeOffset, Viewport},
+		stack, text_input,
 	},
-	iced_widget::{column, row, scrollable},
 	widget::{
 		self, container, horizontal_space,
-		markdown::{self, Item},
-		text_editor::{self, Action, Binding, Edit},
+		text_editor::{self, Action, Edit, Motion},
 		vertical_space,
 	},
 };
@@ -19,25 +28,126 @@ use tracing::{error, info, warn};
 
 use crate::{
 	app::message::{Message, task},
+	md::{
+		self,
+		block::Kind,
+		front_matter::FrontMatter,
+		lint::{Diagnostic, Rule},
+		spell::Misspelling,
+		stats::Stats,
+	},
 	trans,
-	utils::cfg::script::ScriptCfg,
+	utils::{
+		cfg::{
+			flags::Flags,
+			script::{ScriptCfg, font},
+			zoom::Scale,
+		},
+		links::{self, BrokenLink},
+		notes, spell,
+	},
 };
 
 use super::{Screen, format_path};
+use attach::Png;
+use gutter::{Marker, Metrics, Underline, Wrap};
+use history::{Group, History, Snapshot};
+use preview::{Item, diagram::Diagrams, image::Images};
+use prompt::{Prompt, PromptBar};
+use spelling::Suggestions;
+use status::{LineEnding, Status};
+use wiki::NoteBar;
+
+pub mod attach;
+mod buffer;
+pub mod cursors;
+pub mod gutter;
+pub mod history;
+mod keys;
+pub mod metadata;
+pub mod preview;
+pub mod problems;
+pub mod prompt;
+pub mod spelling;
+pub mod status;
+pub mod wiki;
 
 const TAB: char = '\t';
 
+/// How long the text has to stay the same before it is analyzed
+const ANALYSIS_DELAY: Duration = Duration::from_millis(300);
+
 pub struct Editor {
+	/// Unique to each editor, so that work done in the background is sent back to the editor
+	/// that started it
+	id: u64,
 	path: Option<PathBuf>,
 	dirty: bool,
 	default_text: String,
 	text: text_editor::Content,
 	md: Vec<Item>,
+	/// The local images shown in the preview
+	images: Images,
+	/// The diagrams shown in the preview
+	diagrams: Diagrams,
+	history: History,
+	diagnostics: Vec<Diagnostic>,
+	/// The links found by the link checker that do not lead anywhere
+	broken_links: Vec<BrokenLink>,
+	/// Whether the problems panel is shown
+	problems: bool,
+	front_matter: FrontMatter,
+	/// The text of the front matter, so that it is only parsed again once it changes
+	front_matter_source: String,
+	/// Whether the name of the document may have changed since the tab last showed it
+	renamed: bool,
+	/// The fields shown in the metadata panel
+	metadata: Vec<(String, String)>,
+	/// Whether the metadata panel is shown
+	metadata_panel: bool,
+	misspellings: Vec<Misspelling>,
+	/// The language the text was last spell checked in, which suggestions are looked up in
+	language: Option<String>,
+	/// Shown while the cursor is in a misspelled word
+	suggestions: Option<Suggestions>,
+	/// The notes of the workspace, which wiki links are completed from
+	notes: Vec<PathBuf>,
+	/// The line and byte columns of every wiki link, which are underlined
+	note_links: Vec<(usize, Range<usize>)>,
+	/// Shown while the cursor is in a wiki link
+	note_bar: Option<NoteBar>,
+	/// The notes linking to the document
+	backlinks: Vec<PathBuf>,
+	/// Whether the backlinks panel is shown
+	backlinks_panel: bool,
+	/// What each line belongs to, which decides what typing does on it
+	blocks: Vec<Kind>,
+	/// The lines marked in the gutter
+	markers: BTreeMap<usize, Marker>,
+	/// The amount of columns of the longest line, which the editor is as wide as while text is not
+	/// wrapped
+	longest_line: usize,
+	/// How many times the text has changed, so that it is only analyzed once it stops changing
+	edits: u64,
+	/// Cursors in addition to the one of the editor widget, as byte ranges of selected text
+	cursors: Vec<Range<usize>>,
+	/// Where the mouse was clicked to start selecting a rectangle of text
+	column_anchor: Option<(usize, usize)>,
+	modifiers: Modifiers,
+	stats: Stats,
+	line_ending: LineEnding,
+	/// Only used if "zoom_per_tab" is enabled
+	scale: Option<Scale>,
+	/// The scrollable the text is placed in when the gutter is shown
+	scroll: scrollable::Id,
+	preview_scroll: scrollable::Id,
+	viewport: Option<Viewport>,
+	prompt: Option<PromptBar>,
 }
 
 impl Editor {
-	pub fn new(path: Option<PathBuf>) -> Self {
-		let text = if let Some(path) = &path {
+	pub fn new(path: Option<PathBuf>, flags: &Flags) -> Self {
+		let (text, line_ending) = if let Some(path) = &path {
 			let str = match std::fs::read_to_string(path) {
 				Ok(str) => str,
 				Err(e) => {
@@ -45,34 +155,514 @@ impl Editor {
 					"".into()
 				}
 			};
-			text_editor::Content::with_text(&str)
+			(
+				text_editor::Content::with_text(&str),
+				LineEnding::detect(&str),
+			)
+		} else {
+			(text_editor::Content::new(), LineEnding::default())
+		};
+
+		let source: Arc<str> = text.text().into();
+		let snapshot = Snapshot {
+			text: source.clone(),
+			cursor: 0,
+		};
+		let history = match &path {
+			Some(path) if flags.persist_history => History::read(path, snapshot),
+			_ => History::new(snapshot),
+		};
+
+		let md = preview::parse::parse(&source, flags.highlight(), path.as_deref());
+		let diagnostics = if flags.lint {
+			md::lint::lint(&source, &flags.lint_rules(path.as_deref()))
 		} else {
-			text_editor::Content::new()
+			vec![]
 		};
+		let front_matter_source = md::front_matter::source(&source).to_string();
+		let front_matter = md::front_matter::parse(&front_matter_source);
+		let note_links = note_links(&source);
 
-		let md = markdown::parse(&text.text()).collect();
+		static IDS: AtomicU64 = AtomicU64::new(0);
 
-		Self {
+		let mut editor = Self {
+			id: IDS.fetch_add(1, Ordering::Relaxed),
 			path,
 			dirty: false,
 			default_text: trans!("default_text"),
 			text,
 			md,
-		}
+			images: Images::default(),
+			diagrams: Diagrams::default(),
+			history,
+			diagnostics,
+			broken_links: vec![],
+			problems: false,
+			front_matter,
+			front_matter_source,
+			renamed: false,
+			metadata: vec![],
+			metadata_panel: false,
+			misspellings: vec![],
+			language: None,
+			suggestions: None,
+			notes: vec![],
+			note_links,
+			note_bar: None,
+			backlinks: vec![],
+			backlinks_panel: false,
+			blocks: md::block::classify(&source),
+			markers: BTreeMap::new(),
+			longest_line: gutter::longest_line(&source, flags.tab_len()),
+			edits: 0,
+			cursors: vec![],
+			column_anchor: None,
+			modifiers: Modifiers::default(),
+			stats: md::stats::count(&source),
+			line_ending,
+			scale: None,
+			scroll: scrollable::Id::unique(),
+			preview_scroll: scrollable::Id::unique(),
+			viewport: None,
+			prompt: None,
+		};
+		editor.mark(flags);
+		editor
+	}
+
+	pub fn id(&self) -> u64 {
+		self.id
 	}
 
+	/// The title set by the front matter, or the name of the file
 	pub fn name(&self) -> String {
-		if let Some(path) = &self.path {
+		if let Some(title) = self.front_matter.text("title") {
+			title.into()
+		} else if let Some(path) = &self.path {
 			format_path(path)
 		} else {
 			trans!("new_file")
 		}
 	}
 
+	/// Whether the name of the document may have changed since this was last called
+	pub fn take_renamed(&mut self) -> bool {
+		std::mem::take(&mut self.renamed)
+	}
+
 	pub fn can_close(&self) -> bool {
 		!self.dirty
 	}
 
+	fn scale(&self, cfg: &ScriptCfg) -> Scale {
+		if !cfg.flags.zoom_per_tab {
+			return cfg.zoom.global;
+		}
+
+		match self.scale {
+			Some(scale) => scale,
+			None => cfg.zoom.file(self.path.as_ref()),
+		}
+	}
+
+	fn set_scale(&mut self, cfg: &mut ScriptCfg, scale: Scale) {
+		if cfg.flags.zoom_per_tab {
+			self.scale = Some(scale);
+
+			if let Some(path) = &self.path {
+				cfg.zoom.set_file(path.clone(), scale);
+			}
+		} else {
+			cfg.zoom.global = scale;
+		}
+
+		cfg.zoom.write();
+	}
+
+	fn metrics(&self, cfg: &ScriptCfg) -> Metrics {
+		Metrics::new(
+			(cfg.flags.text_size - 1.5) * self.scale(cfg).editor,
+			cfg.flags.editor_font.line_height(),
+			self.text.line_count(),
+			self.longest_line,
+		)
+	}
+
+	/// Checks if the text has to be placed in a scrollable alongside the gutter, line highlight,
+	/// underlines and extra cursors, rather than scrolling itself.
+	///
+	/// Only done while text is not wrapped, since where the editor wraps lines is not known, so
+	/// nothing could be lined up with the lines after the first wrapped one. The font of the editor
+	/// has to be monospace as well, since the width of the text is worked out from its columns.
+	fn scrolls_outside(cfg: &ScriptCfg) -> bool {
+		cfg.flags.wrap() == Wrap::None && cfg.flags.editor_font.is_monospace(font::MONO)
+	}
+
+	/// Marks every line containing an occurrence of the selected text or a problem.
+	///
+	/// Done whenever the text, selection or problems change, rather than every time the gutter
+	/// is rendered.
+	fn mark(&mut self, flags: &Flags) {
+		self.markers.clear();
+		if !flags.markers {
+			return;
+		}
+
+		if let Some(selection) = self.text.selection().filter(|s| !s.trim().is_empty()) {
+			self.markers.extend(
+				(0..self.text.line_count())
+					.filter(|i| {
+						self.text
+							.line(*i)
+							.is_some_and(|line| line.contains(selection.as_str()))
+					})
+					.map(|i| (i, Marker::Occurrence)),
+			);
+		}
+
+		// Problems are more important than occurrences
+		self.markers.extend(
+			self.diagnostics
+				.iter()
+				.map(|diagnostic| (diagnostic.line, Marker::Problem)),
+		);
+	}
+
+	/// The columns a range of a line spans, counting tabs as multiple columns
+	fn display_columns(&self, line: usize, columns: &Range<usize>, tab_len: usize) -> Range<usize> {
+		let line = self
+			.text
+			.line(line)
+			.map(|line| line.to_string())
+			.unwrap_or_default();
+		let width = |end: usize| {
+			line.get(..end.min(line.len()))
+				.unwrap_or_default()
+				.chars()
+				.map(|ch| if ch == '\t' { tab_len } else { 1 })
+				.sum::<usize>()
+		};
+
+		width(columns.start)..width(columns.end)
+	}
+
+	/// Shows suggestions if the cursor is in a misspelled word, hiding them otherwise.
+	///
+	/// Suggestions are only looked for once the cursor enters another misspelled word.
+	fn suggest(&mut self) -> Task<Message> {
+		let (line, column) = self.text.cursor_position();
+
+		let Some(misspelling) = self.misspellings.iter().find(|misspelling| {
+			misspelling.line == line
+				&& (misspelling.columns.start..=misspelling.columns.end).contains(&column)
+		}) else {
+			self.suggestions = None;
+			return Task::none();
+		};
+
+		if self
+			.suggestions
+			.as_ref()
+			.is_some_and(|suggestions| suggestions.misspelling == *misspelling)
+		{
+			return Task::none();
+		}
+
+		self.suggestions = Some(Suggestions {
+			misspelling: misspelling.clone(),
+			suggestions: None,
+		});
+		match &self.language {
+			Some(language) => self.background(Task::future(suggest_words(
+				misspelling.clone(),
+				language.clone(),
+			))),
+			None => Task::none(),
+		}
+	}
+
+	/// Suggests notes while the name of one is being typed in a wiki link, or offers to open the
+	/// note of the link the cursor is in
+	fn complete_note(&mut self, cfg: &ScriptCfg) {
+		let (line, column) = self.text.cursor_position();
+		let line = self
+			.text
+			.line(line)
+			.map(|line| line.to_string())
+			.unwrap_or_default();
+		let before = line.get(..column).unwrap_or(&line);
+
+		if let Some(partial) = md::wiki::completion(before) {
+			self.note_bar = Some(NoteBar::completion(partial, &self.notes));
+			return;
+		}
+
+		let link = md::wiki::find(&line).into_iter().find(|link| {
+			!link.note.is_empty() && link.range.start < column && column < link.range.end
+		});
+
+		self.note_bar = link.map(|link| {
+			let exists = self
+				.workspace(cfg)
+				.and_then(|workspace| notes::find(&self.notes, &workspace, &link.note))
+				.is_some();
+			NoteBar::Link { link, exists }
+		});
+	}
+
+	/// The folder of notes that wiki links point into
+	fn workspace(&self, cfg: &ScriptCfg) -> Option<PathBuf> {
+		cfg.flags.workspace(self.path.as_deref())
+	}
+
+	/// Sends the messages of work done in the background back to this editor, since another tab
+	/// may be active by the time it is done
+	fn background(&self, task: Task<Message>) -> Task<Message> {
+		let id = self.id;
+		task.map(move |action| match action {
+			cosmic::Action::App(message) => {
+				cosmic::Action::App(Message::ToEditor(id, Box::new(message)))
+			}
+			action => action,
+		})
+	}
+
+	/// Lists the notes of the workspace in the background
+	fn list_notes(&self, cfg: &ScriptCfg) -> Task<Message> {
+		match self.workspace(cfg) {
+			Some(workspace) => self.background(Task::future(list_notes(workspace))),
+			None => Task::none(),
+		}
+	}
+
+	/// Finds the notes linking to the document in the background
+	fn find_backlinks(&self, cfg: &ScriptCfg) -> Task<Message> {
+		match (self.workspace(cfg), self.path.clone()) {
+			(Some(workspace), Some(path)) => {
+				self.background(Task::future(find_backlinks(workspace, path)))
+			}
+			_ => Task::none(),
+		}
+	}
+
+	/// Scrolls the text so that the cursor is visible.
+	///
+	/// Only needed when the text does not scroll itself.
+	fn scroll_to_cursor(&self, metrics: &Metrics) -> Task<Message> {
+		let Some(viewport) = self.viewport else {
+			return Task::none();
+		};
+
+		let offset = viewport.absolute_offset().y;
+		let height = viewport.bounds().height;
+
+		let (line, _) = self.text.cursor_position();
+		let top = metrics.line_y(line) - gutter::PADDING;
+		let bottom = metrics.line_y(line + 1) + gutter::PADDING;
+
+		let y = if top < offset {
+			top
+		} else if bottom > offset + height {
+			bottom - height
+		} else {
+			return Task::none();
+		};
+
+		let x = viewport.absolute_offset().x;
+		scrollable::scroll_to(self.scroll.clone(), AbsoluteOffset { x, y })
+	}
+
+	/// Marks the text as unsaved, then updates the preview and statistics and checks for problems
+	/// and misspelled words in the background once the text stops changing
+	fn changed(&mut self, cfg: &ScriptCfg) -> Task<Message> {
+		self.dirty = true;
+		self.cursors.clear();
+		self.mark(&cfg.flags);
+
+		// The text is only copied out of the editor once, and shared with the history and analyses
+		let text: Arc<str> = self.text.text().into();
+		let (line, column) = self.text.cursor_position();
+
+		self.read_front_matter(cfg, &text);
+		self.note_links = note_links(&text);
+		self.blocks = md::block::classify(&text);
+		self.longest_line = gutter::longest_line(&text, cfg.flags.tab_len());
+		self.history.record(
+			Snapshot {
+				text: text.clone(),
+				cursor: buffer::offset(&text, line, column),
+			},
+			cfg.flags.undo_limit(),
+		);
+
+		self.edits += 1;
+		self.background(Task::future(wait(self.edits, text)))
+	}
+
+	/// Like [Self::changed], but keeps the extra cursors, since the change was made at all of them
+	fn changed_at_cursors(&mut self, cfg: &ScriptCfg) -> Task<Message> {
+		let cursors = std::mem::take(&mut self.cursors);
+		let changed = self.changed(cfg);
+		self.cursors = cursors;
+		changed
+	}
+
+	/// Updates the preview and statistics and checks for problems and misspelled words
+	fn analyze(&mut self, cfg: &ScriptCfg, text: Arc<str>) -> Task<Message> {
+		let mut tasks = vec![Task::future(parse_md(
+			text.clone(),
+			cfg.flags.highlight(),
+			self.path.clone(),
+		))];
+		if !cfg.flags.status_bar.is_empty() {
+			tasks.push(Task::future(count_md(text.clone())));
+		}
+		tasks.push(self.lint(cfg, text.clone()));
+
+		let spell_check = self.spell_check(cfg, text);
+		Task::batch([self.background(Task::batch(tasks)), spell_check])
+	}
+
+	/// Spell checks the text in the language set by its front matter or the config
+	fn spell_check(&mut self, cfg: &ScriptCfg, text: Arc<str>) -> Task<Message> {
+		self.language = spell_language(&text, &cfg.flags);
+
+		match &self.language {
+			Some(language) => self.background(Task::future(spell_md(text, language.clone()))),
+			None => {
+				self.misspellings.clear();
+				self.suggestions = None;
+				Task::none()
+			}
+		}
+	}
+
+	/// Lints the text with the rules enabled for the file, if linting is enabled
+	fn lint(&mut self, cfg: &ScriptCfg, text: Arc<str>) -> Task<Message> {
+		let rules = cfg.flags.lint_rules(self.path.as_deref());
+		if !cfg.flags.lint || rules.is_empty() {
+			self.diagnostics.clear();
+			self.mark(&cfg.flags);
+			return Task::none();
+		}
+
+		Task::future(lint_md(text, rules))
+	}
+
+	/// Parses the front matter if its text changed, updating the metadata panel and the name of
+	/// the document if its fields changed
+	fn read_front_matter(&mut self, cfg: &ScriptCfg, text: &str) {
+		let source = md::front_matter::source(text);
+		if source == self.front_matter_source {
+			return;
+		}
+		self.front_matter_source = source.into();
+
+		let front_matter = md::front_matter::parse(source);
+		if front_matter != self.front_matter {
+			self.renamed |= front_matter.text("title") != self.front_matter.text("title");
+			self.front_matter = front_matter;
+			self.metadata = metadata::fields(cfg, &self.front_matter);
+		}
+	}
+
+	/// Moves the cursor to the heading with the anchor, scrolling the preview to it
+	fn scroll_to_anchor(&mut self, cfg: &ScriptCfg, anchor: &str) -> Task<Message> {
+		let text = self.text.text();
+		let Some(line) = md::anchor::find(&text, anchor) else {
+			warn!("Heading #{anchor} not found");
+			return Task::none();
+		};
+		self.move_to(buffer::line_start(&text, line));
+
+		// Where the heading is rendered is not known, so the preview is scrolled as far as the
+		// heading is into the text
+		let y = line as f32 / self.text.line_count().max(1) as f32;
+		let preview = scrollable::snap_to(self.preview_scroll.clone(), RelativeOffset { x: 0., y });
+
+		if Self::scrolls_outside(cfg) {
+			Task::batch([preview, self.scroll_to_cursor(&self.metrics(cfg))])
+		} else {
+			preview
+		}
+	}
+
+	/// Loads the images and renders the diagrams of the preview that are not cached yet
+	fn load_media(&mut self, cfg: &ScriptCfg) -> Task<Message> {
+		let task = Task::batch([
+			self.images.load(&self.md, cfg.flags.max_image_size()),
+			self.diagrams.render(&self.md, &cfg.flags),
+		]);
+		self.background(task)
+	}
+
+	/// Formats the whole document, keeping the cursor on the same line and column.
+	///
+	/// Returns false if it was already formatted.
+	fn format_document(&mut self, cfg: &ScriptCfg) -> bool {
+		let text = self.text.text();
+		let formatted = md::fmt::format(&text, &cfg.format_options);
+		if formatted == text {
+			return false;
+		}
+
+		let (line, column) = self.text.cursor_position();
+		self.set_text(&formatted, buffer::offset(&formatted, line, column));
+		true
+	}
+
+	/// Places the delimiters around the selection, keeping the text between them selected.
+	///
+	/// Places the cursor between them if nothing is selected.
+	fn surround(&mut self, open: &str, close: &str) {
+		let paste = |text: &str| Action::Edit(Edit::Paste(Arc::new(text.into())));
+
+		let Some(selection) = self.text.selection() else {
+			self.text.perform(paste(&format!("{open}{close}")));
+			for _ in close.chars() {
+				self.text.perform(Action::Move(Motion::Left));
+			}
+			return;
+		};
+
+		let len = selection.chars().count();
+
+		// Moving left with a selection goes to its start
+		self.text.perform(Action::Move(Motion::Left));
+		self.text.perform(paste(open));
+		for _ in 0..len {
+			self.text.perform(Action::Move(Motion::Right));
+		}
+		self.text.perform(paste(close));
+
+		for _ in close.chars() {
+			self.text.perform(Action::Move(Motion::Left));
+		}
+		for _ in 0..len {
+			self.text.perform(Action::Select(Motion::Left));
+		}
+	}
+
+	fn status(&self, cfg: &ScriptCfg) -> Status {
+		let (line, column) = self.text.cursor_position();
+		let column = self
+			.text
+			.line(line)
+			.map(|text| text.get(..column).unwrap_or(&text).chars().count())
+			.unwrap_or_default();
+
+		Status {
+			stats: self.stats,
+			selection: self
+				.text
+				.selection()
+				.map(|selection| md::stats::count(&selection)),
+			cursor: (line + 1, column + 1),
+			line_ending: self.line_ending,
+			words_per_minute: cfg.flags.reading_speed(),
+		}
+	}
+
 	/// Slightly hacky way to insert hard tabs
 	fn hard_tab_hack(&mut self) {
 		let action1 = Action::Edit(Edit::Paste(Arc::new("\ta".into())));
@@ -84,26 +674,162 @@ impl Editor {
 
 impl Screen for Editor {
 	fn view<'cfg>(&'cfg self, cfg: &'cfg ScriptCfg) -> Element<'cfg, Message> {
+		let scale = self.scale(cfg);
+		let metrics = self.metrics(cfg);
 		let space = horizontal_space().width(cfg.flags.text_size);
 
 		let editor = widget::text_editor(&self.text)
-			.key_binding(|kp| key_bindings(kp, cfg))
+			.key_binding(|kp| self.key_bindings(kp, cfg))
 			.placeholder(&self.default_text)
-			.size(cfg.flags.text_size - 1.5)
-			.font(Font::MONOSPACE)
+			.size(metrics.size)
+			.font(cfg.flags.editor_font.to_cosmic(font::MONO))
+			.line_height(cfg.flags.editor_font.line_height())
 			.highlight("markdown", cfg.flags.highlight())
-			.height(Length::Fill)
-			.padding(10)
+			.padding(gutter::PADDING)
 			.on_action(Message::Edit);
 
-		let markdown = markdown::view(
-			self.md.iter(),
-			markdown::Settings::with_text_size(cfg.flags.text_size),
-			markdown::Style::from_palette(cfg.flags.palette),
-		)
-		.map(Message::Url);
+		let editor = match cfg.flags.wrap() {
+			Wrap::None if Self::scrolls_outside(cfg) => {
+				editor.wrapping(Wrapping::None).width(metrics.text_width())
+			}
+			// The text cannot be measured, so lines wider than the editor are cut off
+			Wrap::None => editor.wrapping(Wrapping::None),
+			Wrap::Window => editor.wrapping(Wrapping::WordOrGlyph),
+			Wrap::Column(columns) => editor
+				.wrapping(Wrapping::WordOrGlyph)
+				.width(metrics.columns_width(columns)),
+		};
+
+		let height = if Self::scrolls_outside(cfg) {
+			Length::Shrink
+		} else {
+			Length::Fill
+		};
+
+		let (cursor_line, _) = self.text.cursor_position();
+
+		let mut text = stack![editor.height(height)];
+		if let Some(column) = cfg.flags.ruler() {
+			text = text.push(gutter::ruler(&cfg.flags, &metrics, column));
+		}
+		if Self::scrolls_outside(cfg) {
+			if cfg.flags.highlight_line {
+				text = text.push(gutter::highlight(&cfg.flags, &metrics, cursor_line));
+			}
+
+			let underlines = self
+				.diagnostics
+				.iter()
+				.map(|diagnostic| (diagnostic.line, &diagnostic.columns, Underline::Straight))
+				.chain(self.misspellings.iter().map(|misspelling| {
+					(misspelling.line, &misspelling.columns, Underline::Squiggly)
+				}))
+				.chain(
+					self.note_links
+						.iter()
+						.map(|(line, columns)| (*line, columns, Underline::Link)),
+				);
 
-		row![
+			let content = self.text.text();
+			for cursor in &self.cursors {
+				let start = buffer::position(&content, cursor.start);
+				let end = buffer::position(&content, cursor.end);
+
+				// Selections spanning multiple lines are highlighted on each of them
+				for line in start.0..=end.0 {
+					let from = if line == start.0 { start.1 } else { 0 };
+					let to = if line == end.0 { end.1 } else { usize::MAX };
+					let columns = self.display_columns(line, &(from..to), cfg.flags.tab_len());
+					text = text.push(gutter::cursor(&cfg.flags, &metrics, line, columns));
+				}
+			}
+
+			for (line, columns, style) in underlines {
+				let columns = self.display_columns(line, columns, cfg.flags.tab_len());
+				text = text.push(gutter::underline(
+					&cfg.flags, &metrics, line, columns, style,
+				));
+			}
+		}
+
+		let editor: Element<Message> = if Self::scrolls_outside(cfg) {
+			// Lines are not wrapped, so they may be wider than the editor
+			let direction = scrollable::Direction::Both {
+				vertical: scrollable::Scrollbar::default(),
+				horizontal: scrollable::Scrollbar::default(),
+			};
+
+			scrollable(row![
+				gutter::view(&cfg.flags, &metrics, cursor_line, &self.markers),
+				text,
+			])
+			.id(self.scroll.clone())
+			.on_scroll(Message::EditorScrolled)
+			.direction(direction)
+			.height(Length::Fill)
+			.into()
+		} else {
+			text.into()
+		};
+
+		let editor: Element<Message> = match &self.note_bar {
+			Some(bar) => column![bar.view(cfg.flags.text_size), editor]
+				.spacing(10)
+				.into(),
+			None => editor,
+		};
+
+		let editor: Element<Message> = match &self.suggestions {
+			Some(suggestions) => column![suggestions.view(cfg.flags.text_size), editor]
+				.spacing(10)
+				.into(),
+			None => editor,
+		};
+
+		let editor: Element<Message> = match &self.prompt {
+			Some(bar) => column![bar.view(cfg.flags.text_size), editor]
+				.spacing(10)
+				.into(),
+			None => editor,
+		};
+
+		let editor: Element<Message> = if self.problems {
+			column![
+				editor,
+				problems::view(
+					&self.diagnostics,
+					&self.broken_links,
+					self.path.as_deref(),
+					cfg.flags.text_size
+				)
+			]
+			.spacing(10)
+			.into()
+		} else {
+			editor
+		};
+
+		let fields = cfg.flags.status_bar();
+		let editor: Element<Message> = if fields.is_empty() {
+			editor
+		} else {
+			column![
+				editor,
+				self.status(cfg).view(&fields, cfg.flags.text_size * 0.8)
+			]
+			.spacing(10)
+			.into()
+		};
+
+		let markdown = preview::view(
+			&self.md,
+			&self.images,
+			&self.diagrams,
+			&cfg.flags,
+			scale.preview,
+		);
+
+		let page = row![
 			container(editor).padding(10),
 			row![
 				space,
@@ -111,53 +837,507 @@ impl Screen for Editor {
 					markdown,
 					vertical_space().height(cfg.flags.text_size * 10.)
 				])
+				.id(self.preview_scroll.clone())
 				.width(Length::Fill)
 				.height(Length::Fill)
 				.spacing(cfg.flags.text_size)
 			]
-		]
-		.into()
+		];
+
+		let page = if self.metadata_panel {
+			page.push(container(metadata::view(&self.metadata, cfg.flags.text_size)).padding(10))
+		} else {
+			page
+		};
+
+		if self.backlinks_panel {
+			page.push(container(wiki::backlinks(&self.backlinks, cfg.flags.text_size)).padding(10))
+				.into()
+		} else {
+			page.into()
+		}
 	}
 
-	fn update<'cfg>(&'cfg mut self, _: &'cfg mut ScriptCfg, message: Message) -> Task<Message> {
+	fn update<'cfg>(&'cfg mut self, cfg: &'cfg mut ScriptCfg, message: Message) -> Task<Message> {
 		match message {
 			Message::Save => {
 				let Some(path) = self.path.clone() else {
 					return task(Message::SaveAsFilePicker);
 				};
 
-				if let Err(e) = std::fs::write(&path, self.text.text()) {
+				let reparse = if cfg.flags.format_on_save && self.format_document(cfg) {
+					self.changed(cfg)
+				} else {
+					Task::none()
+				};
+
+				if let Err(e) = std::fs::write(&path, self.line_ending.apply(self.text.text())) {
 					error!("Error when saving: {e}");
 				} else {
 					self.dirty = false;
 					info!("File {:?} saved successfully!", path);
+
+					if cfg.flags.persist_history {
+						self.history.write(&path);
+					}
+				}
+
+				return reparse;
+			}
+
+			Message::ExportHtml => {
+				let Some(path) = &self.path else {
+					warn!("Only documents that have been saved can be exported");
+					return Task::none();
+				};
+
+				let html = path.with_extension("html");
+				let title = path.file_stem().unwrap_or_default().to_string_lossy();
+				match std::fs::write(&html, md::html::export(&self.text.text(), &title)) {
+					Ok(()) => info!("Exported {html:?}"),
+					Err(e) => error!("Error when exporting: {e}"),
 				}
 			}
 
 			Message::SaveAs(path) => {
 				self.path = Some(path);
+				self.renamed = true;
 				return task(Message::Save);
 			}
 
 			Message::Edit(action) => {
 				let is_edit = action.is_edit();
+				self.history.group(Group::of(&action));
+
+				if !self.perform_at_cursors(&action, Self::scrolls_outside(cfg)) {
+					if let Action::Edit(Edit::Insert(TAB)) = action {
+						self.hard_tab_hack();
+					} else {
+						self.text.perform(action);
+					}
+				}
+				let suggest = self.suggest();
+				self.complete_note(cfg);
 
-				if let Action::Edit(Edit::Insert(TAB)) = action {
-					self.hard_tab_hack();
+				let scroll = if Self::scrolls_outside(cfg) {
+					Task::batch([suggest, self.scroll_to_cursor(&self.metrics(cfg))])
 				} else {
+					suggest
+				};
+
+				if is_edit {
+					return Task::batch([scroll, self.changed_at_cursors(cfg)]);
+				}
+
+				// The selection may have changed
+				self.mark(&cfg.flags);
+				return scroll;
+			}
+
+			// Extra cursors cannot be shown while they would not line up with the text
+			Message::Cursors(command)
+				if command != cursors::Command::Clear && !Self::scrolls_outside(cfg) => {}
+			Message::Cursors(command) => {
+				self.cursor_command(command);
+				self.mark(&cfg.flags);
+			}
+
+			Message::ModifiersChanged(modifiers) => self.modifiers = modifiers,
+
+			Message::Undo => {
+				if let Some(snapshot) = self.history.undo() {
+					self.set_text(&snapshot.text, snapshot.cursor);
+					return self.changed(cfg);
+				}
+			}
+
+			Message::Redo => {
+				if let Some(snapshot) = self.history.redo() {
+					self.set_text(&snapshot.text, snapshot.cursor);
+					return self.changed(cfg);
+				}
+			}
+
+			Message::Reflow => {
+				let text = self.text.text();
+				let lines = self.selected_lines();
+
+				if let Some((text, reflowed)) =
+					md::reflow::reflow(&text, lines, cfg.flags.reflow_width())
+				{
+					// The cursor ends up after the last reflowed paragraph
+					let cursor = buffer::line_end(&text, reflowed.end - 1);
+					self.set_text(&text, cursor);
+					return self.changed(cfg);
+				}
+			}
+
+			Message::FormatDocument => {
+				if self.format_document(cfg) {
+					return self.changed(cfg);
+				}
+			}
+
+			Message::Surround(open, close) => {
+				self.surround(&open, &close);
+				return self.changed(cfg);
+			}
+
+			Message::ToggleStyle(style) => {
+				let change = md::format::toggle(&self.text.text(), self.selection_range(), style);
+				self.apply(change);
+				return self.changed(cfg);
+			}
+
+			Message::ToggleCodeBlock => {
+				let change =
+					md::format::toggle_code_block(&self.text.text(), self.selection_range());
+				self.apply(change);
+				return self.changed(cfg);
+			}
+
+			Message::ToggleLink => {
+				let Some(change) = md::format::unlink(&self.text.text(), self.cursor_offset())
+				else {
+					return task(Message::OpenPrompt(Prompt::Link));
+				};
+
+				self.apply(change);
+				return self.changed(cfg);
+			}
+
+			Message::ShiftHeading(amount) => {
+				let change =
+					md::format::shift_heading(&self.text.text(), self.selection_range(), amount);
+				self.apply(change);
+				return self.changed(cfg);
+			}
+
+			Message::Table(command) => {
+				let text = self.text.text();
+
+				if let Some(change) = md::table::run(&text, self.cursor_offset(), command) {
+					self.apply(change);
+					return self.changed(cfg);
+				}
+			}
+
+			Message::Paste => {
+				return clipboard::read().map(|text| cosmic::Action::App(Message::Pasted(text)));
+			}
+
+			Message::Pasted(Some(text)) => {
+				let (line, _) = self.text.cursor_position();
+				let on_blank_line = self
+					.text
+					.line(line)
+					.is_some_and(|line| line.trim().is_empty());
+
+				// Values are only turned into a table if it can be placed on its own line
+				let text = match md::table::from_delimited(&text) {
+					Some(table)
+						if cfg.flags.paste_tables
+							&& on_blank_line && self.text.selection().is_none()
+							&& self.cursors.is_empty() =>
+					{
+						table
+					}
+					_ => text,
+				};
+
+				// Pasted at every cursor, like typed text
+				let action = Action::Edit(Edit::Paste(Arc::new(text)));
+				if !self.perform_at_cursors(&action, Self::scrolls_outside(cfg)) {
 					self.text.perform(action);
 				}
+				return self.changed_at_cursors(cfg);
+			}
 
-				if is_edit {
-					self.dirty = true;
-					return Task::future(parse_md(self.text.text()));
+			// The clipboard may contain an image instead
+			Message::Pasted(None) => {
+				return clipboard::read_data::<Png>()
+					.map(|image| cosmic::Action::App(Message::PastedImage(image)));
+			}
+
+			Message::PastedImage(Some(image)) => {
+				if let Some(link) = attach::paste(&cfg.flags, self.path.as_deref(), &image) {
+					self.text.perform(Action::Edit(Edit::Paste(Arc::new(link))));
+					return self.changed(cfg);
+				}
+			}
+
+			Message::FileDropped(path) if attach::is_image(&path) => {
+				if let Some(link) = attach::dropped(&cfg.flags, self.path.as_deref(), &path) {
+					self.text.perform(Action::Edit(Edit::Paste(Arc::new(link))));
+					return self.changed(cfg);
 				}
 			}
 
+			Message::OpenPrompt(prompt) => {
+				let bar = PromptBar::new(prompt);
+				let focus = text_input::focus(bar.id.clone());
+				self.prompt = Some(bar);
+				return focus;
+			}
+
+			Message::PromptInput(value) => {
+				if let Some(bar) = &mut self.prompt {
+					bar.value = value;
+				}
+			}
+
+			Message::PromptSubmit => {
+				let Some(bar) = self.prompt.take() else {
+					return Task::none();
+				};
+
+				if bar.value.is_empty() {
+					return Task::none();
+				}
+
+				match bar.prompt {
+					Prompt::Surround => {
+						self.surround(&bar.value, &md::pairs::closing(&bar.value));
+					}
+					Prompt::Link => {
+						let text = self.text.text();
+						self.apply(md::format::link(&text, self.selection_range(), &bar.value));
+					}
+				}
+
+				return self.changed(cfg);
+			}
+
+			Message::PromptCancel => self.prompt = None,
+
+			Message::EditorScrolled(viewport) => {
+				self.viewport = Some(viewport);
+			}
+
 			Message::Parsed(md) => {
 				self.md = md;
+				return self.load_media(cfg);
+			}
+
+			// The images and diagrams of a newly opened file are loaded once it has been added to
+			// the tabs, and its front matter is passed to the script
+			Message::OpenEditor(_) | Message::OpenLink(_, None) => {
+				self.metadata = metadata::fields(cfg, &self.front_matter);
+				return Task::batch([
+					self.load_media(cfg),
+					self.list_notes(cfg),
+					self.spell_check(cfg, self.text.text().into()),
+				]);
+			}
+
+			Message::OpenAt(_, line, column) => {
+				self.metadata = metadata::fields(cfg, &self.front_matter);
+				return Task::batch([
+					self.load_media(cfg),
+					self.list_notes(cfg),
+					self.spell_check(cfg, self.text.text().into()),
+					task(Message::GoTo(line, column)),
+				]);
+			}
+
+			Message::OpenLink(_, Some(anchor)) => {
+				self.metadata = metadata::fields(cfg, &self.front_matter);
+				return Task::batch([
+					self.load_media(cfg),
+					self.list_notes(cfg),
+					self.spell_check(cfg, self.text.text().into()),
+					self.scroll_to_anchor(cfg, &anchor),
+				]);
+			}
+
+			Message::OpenNote(note, anchor) => {
+				let Some(workspace) = self.workspace(cfg) else {
+					warn!("Wiki links need a saved document or a workspace to lead somewhere");
+					return Task::none();
+				};
+
+				// The notes are listed again, since they may have changed since they were last listed
+				let path = match notes::find(&notes::list(&workspace), &workspace, &note) {
+					Some(path) => path,
+					None => match notes::create(&workspace, &note) {
+						Some(path) => path,
+						None => return Task::none(),
+					},
+				};
+
+				self.note_bar = None;
+				return Task::batch([self.list_notes(cfg), task(Message::OpenLink(path, anchor))]);
+			}
+
+			Message::NotesListed(notes) => {
+				self.notes = notes;
+			}
+
+			Message::CompleteNote(name) => {
+				let text = self.text.text();
+				let cursor = self.cursor_offset();
+				let Some(partial) = md::wiki::completion(&text[..cursor]) else {
+					return Task::none();
+				};
+
+				// The brackets may already be closed by auto pairs
+				let start = cursor - partial.len();
+				let close = if text[cursor..].starts_with("]]") {
+					""
+				} else {
+					"]]"
+				};
+
+				let text = format!("{}{name}{close}{}", &text[..start], &text[cursor..]);
+				self.set_text(&text, start + name.len() + 2);
+				self.note_bar = None;
+				return self.changed(cfg);
+			}
+
+			Message::ScrollToAnchor(anchor) => return self.scroll_to_anchor(cfg, &anchor),
+
+			Message::ImageLoaded(path, modified, image) => {
+				self.images.loaded(path, modified, image);
+			}
+
+			Message::DiagramRendered(hash, diagram) => {
+				self.diagrams.rendered(hash, diagram);
+			}
+
+			// Only the last of the changes made in a row is analyzed
+			Message::Analyze(edit, text) if edit == self.edits => return self.analyze(cfg, text),
+			Message::Analyze(..) => (),
+
+			Message::Counted(stats) => {
+				self.stats = stats;
+			}
+
+			Message::Linted(diagnostics) => {
+				self.diagnostics = diagnostics;
+				self.mark(&cfg.flags);
+			}
+
+			Message::SpellChecked(misspellings) => {
+				self.misspellings = misspellings;
+				return self.suggest();
+			}
+
+			Message::Suggested(misspelling, words) => {
+				// The cursor may have left the word since
+				if let Some(suggestions) = self
+					.suggestions
+					.as_mut()
+					.filter(|suggestions| suggestions.misspelling == misspelling)
+				{
+					suggestions.suggestions = Some(words);
+				}
+			}
+
+			Message::ReplaceWord(word) => {
+				let Some(suggestions) = self.suggestions.take() else {
+					return Task::none();
+				};
+				let misspelling = suggestions.misspelling;
+
+				let text = self.text.text();
+				let start = buffer::line_start(&text, misspelling.line) + misspelling.columns.start;
+				let end = start + misspelling.columns.len();
+
+				// The text may have changed since it was checked
+				if text.get(start..end) != Some(misspelling.word.as_str()) {
+					return Task::none();
+				}
+
+				let text = format!("{}{word}{}", &text[..start], &text[end..]);
+				self.set_text(&text, start + word.len());
+				return self.changed(cfg);
+			}
+
+			Message::AddToDictionary => {
+				if let Some(suggestions) = self.suggestions.take() {
+					let word = suggestions.misspelling.word;
+					spell::add_to_personal(&word);
+					self.misspellings
+						.retain(|misspelling| misspelling.word != word);
+				}
+			}
+
+			Message::ToggleProblems => {
+				self.problems = !self.problems;
+			}
+
+			Message::ToggleMetadata => {
+				self.metadata_panel = !self.metadata_panel;
+			}
+
+			Message::CheckLinks(everywhere) => {
+				let workspace = self.workspace(cfg).filter(|_| everywhere);
+				if everywhere && workspace.is_none() {
+					warn!("Only saved documents have a workspace to check, unless one is set");
+					return Task::none();
+				}
+
+				// Checking a workspace can take a while, during which another tab may be opened
+				return self.background(Task::future(check_links(
+					self.path.clone(),
+					self.text.text(),
+					workspace,
+					cfg.flags.check_external_links,
+				)));
+			}
+
+			Message::LinksChecked(broken_links) => {
+				info!("Found {} broken links", broken_links.len());
+				self.broken_links = broken_links;
+				self.problems = true;
+			}
+
+			Message::ToggleBacklinks => {
+				self.backlinks_panel = !self.backlinks_panel;
+
+				if self.backlinks_panel {
+					return self.find_backlinks(cfg);
+				}
+			}
+
+			Message::BacklinksFound(backlinks) => {
+				self.backlinks = backlinks;
+			}
+
+			Message::GoTo(line, column) => {
+				let text = self.text.text();
+				self.move_to(buffer::offset(&text, line, column));
+				self.mark(&cfg.flags);
+				let suggest = self.suggest();
+				self.complete_note(cfg);
+
+				if Self::scrolls_outside(cfg) {
+					return Task::batch([suggest, self.scroll_to_cursor(&self.metrics(cfg))]);
+				}
+				return suggest;
+			}
+
+			Message::ToggleTask(offset) => {
+				// The preview may be out of date, in which case the checkbox is not found
+				if let Some(text) = md::list::toggle_task(&self.text.text(), offset) {
+					let cursor = self.cursor_offset();
+					self.set_text(&text, cursor);
+					return self.changed(cfg);
+				}
 			}
 
+			Message::ZoomIn(pane) => {
+				let scale = self.scale(cfg).zoom(pane, cfg.flags.zoom_step);
+				self.set_scale(cfg, scale);
+			}
+
+			Message::ZoomOut(pane) => {
+				let scale = self.scale(cfg).zoom(pane, -cfg.flags.zoom_step);
+				self.set_scale(cfg, scale);
+			}
+
+			Message::ZoomReset => self.set_scale(cfg, Scale::default()),
+
 			Message::Url(url) => {
 				info!("Opening {}", url.as_str());
 
@@ -173,25 +1353,130 @@ impl Screen for Editor {
 	}
 }
 
-fn key_bindings(kp: text_editor::KeyPress, flags: &ScriptCfg) -> Option<Binding<Message>> {
-	// TODO: Custom bindings; Vim/Helix motions
+async fn parse_md(text: Arc<str>, theme: Theme, path: Option<PathBuf>) -> cosmic::Action<Message> {
+	let items = blocking(move || preview::parse::parse(&text, theme, path.as_deref())).await;
+	cosmic::Action::App(Message::Parsed(items))
+}
 
-	if let keyboard::Key::Named(Named::Tab) = kp.key {
-		// Tabs
+async fn list_notes(workspace: PathBuf) -> cosmic::Action<Message> {
+	cosmic::Action::App(Message::NotesListed(notes::list(&workspace)))
+}
 
-		let binding = if flags.flags.expand_tabs {
-			Binding::Sequence(vec![Binding::Insert(' '); flags.flags.tab_len()])
-		} else {
-			Binding::Insert(TAB)
-		};
+async fn find_backlinks(workspace: PathBuf, path: PathBuf) -> cosmic::Action<Message> {
+	let notes = notes::list(&workspace);
+	cosmic::Action::App(Message::BacklinksFound(notes::backlinks(
+		&notes, &workspace, &path,
+	)))
+}
+
+/// Checks the links of the document, followed by every other note of the workspace if there is one.
+///
+/// Reading the notes and requesting URLs blocks, so it is done on a thread of its own.
+async fn check_links(
+	path: Option<PathBuf>,
+	text: String,
+	workspace: Option<PathBuf>,
+	external: bool,
+) -> cosmic::Action<Message> {
+	let check = move || {
+		let mut checker = links::Checker::new(external);
+		let mut broken = checker.check(path.as_deref(), &text);
 
-		Some(binding)
-	} else {
-		// Default bindings
-		Binding::from_key_press(kp)
+		let others = workspace
+			.map(|workspace| notes::list(&workspace))
+			.unwrap_or_default()
+			.into_iter()
+			.filter(|note| Some(note) != path.as_ref());
+		for note in others {
+			match std::fs::read_to_string(&note) {
+				Ok(text) => broken.extend(checker.check(Some(&note), &text)),
+				Err(e) => warn!("Could not read {note:?}: {e}"),
+			}
+		}
+
+		broken
+	};
+
+	let broken = tokio::task::spawn_blocking(check)
+		.await
+		.unwrap_or_else(|e| {
+			error!("Links could not be checked: {e}");
+			vec![]
+		});
+	cosmic::Action::App(Message::LinksChecked(broken))
+}
+
+/// Waits for the text to stop changing before analyzing it
+async fn wait(edit: u64, text: Arc<str>) -> cosmic::Action<Message> {
+	tokio::time::sleep(ANALYSIS_DELAY).await;
+	cosmic::Action::App(Message::Analyze(edit, text))
+}
+
+async fn count_md(text: Arc<str>) -> cosmic::Action<Message> {
+	let stats = blocking(move || md::stats::count(&text)).await;
+	cosmic::Action::App(Message::Counted(stats))
+}
+
+async fn lint_md(text: Arc<str>, rules: HashSet<Rule>) -> cosmic::Action<Message> {
+	let diagnostics = blocking(move || md::lint::lint(&text, &rules)).await;
+	cosmic::Action::App(Message::Linted(diagnostics))
+}
+
+async fn spell_md(text: Arc<str>, language: String) -> cosmic::Action<Message> {
+	let misspellings = blocking(move || check_spelling(&text, &language)).await;
+	cosmic::Action::App(Message::SpellChecked(misspellings))
+}
+
+async fn suggest_words(misspelling: Misspelling, language: String) -> cosmic::Action<Message> {
+	let word = misspelling.word.clone();
+	let suggestions = blocking(move || {
+		spell::dictionary(&language)
+			.map(|dictionary| spell::suggest(&dictionary, &word))
+			.unwrap_or_default()
+	})
+	.await;
+	cosmic::Action::App(Message::Suggested(misspelling, suggestions))
+}
+
+/// Runs work that keeps the CPU busy on a thread of its own, so that other tasks are not held up
+async fn blocking<T>(work: impl FnOnce() -> T + Send + 'static) -> T
+where
+	T: Default + Send + 'static,
+{
+	tokio::task::spawn_blocking(work).await.unwrap_or_else(|e| {
+		error!("Document could not be analyzed: {e}");
+		T::default()
+	})
+}
+
+/// The line and byte columns of every wiki link
+fn note_links(text: &str) -> Vec<(usize, Range<usize>)> {
+	md::wiki::links(text)
+		.into_iter()
+		.map(|link| {
+			let (line, start) = buffer::position(text, link.range.start);
+			(line, start..start + link.range.len())
+		})
+		.collect()
+}
+
+fn check_spelling(text: &str, language: &str) -> Vec<Misspelling> {
+	match spell::dictionary(language) {
+		Some(dictionary) => md::spell::check(text, |word| spell::is_correct(&dictionary, word)),
+		None => vec![],
 	}
 }
 
-async fn parse_md(text: String) -> cosmic::Action<Message> {
-	cosmic::Action::App(Message::Parsed(markdown::parse(&text).collect()))
+/// The language the text is spell checked in, which is set by its front matter or the config.
+///
+/// Returns None if spell checking is disabled.
+fn spell_language(text: &str, flags: &Flags) -> Option<String> {
+	if !flags.spell_check {
+		return None;
+	}
+
+	let language = md::spell::language(text)
+		.or_else(|| Some(flags.spell_language.trim().to_string()).filter(|l| !l.is_empty()))
+		.unwrap_or_else(trans::system_language);
+	Some(language)
 }
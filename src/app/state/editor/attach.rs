@@ -0,0 +1,172 @@
+//! Places pasted and dropped images next to the document, linking to them from it
+
+use std::{
+	borrow::Cow,
+	fs,
+	path::{Component, Path, PathBuf},
+};
+
+use cosmic::iced::clipboard::mime::AllowedMimeTypes;
+use tracing::{error, warn};
+
+use crate::utils::cfg::flags::Flags;
+
+/// The extensions of files that are linked as images when dropped onto the editor
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "webp", "bmp", "svg"];
+
+/// A PNG image read from the clipboard
+#[derive(Debug, Clone)]
+pub struct Png(pub Vec<u8>);
+
+/// What happens to image files dropped onto the editor
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Drop {
+	/// Copies the file next to the document, like pasted images
+	Copy,
+	/// Links to the file where it is
+	Link,
+}
+
+impl AllowedMimeTypes for Png {
+	fn allowed() -> Cow<'static, [String]> {
+		Cow::Owned(vec!["image/png".into()])
+	}
+}
+
+impl TryFrom<(Vec<u8>, String)> for Png {
+	type Error = ();
+
+	fn try_from((bytes, _): (Vec<u8>, String)) -> Result<Self, Self::Error> {
+		Ok(Self(bytes))
+	}
+}
+
+pub fn is_image(path: &Path) -> bool {
+	path.extension()
+		.and_then(|ext| ext.to_str())
+		.is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Saves a pasted image, returning the Markdown linking to it
+pub fn paste(flags: &Flags, document: Option<&Path>, image: &Png) -> Option<String> {
+	let Some(document) = document else {
+		warn!("Images can only be pasted into documents that have been saved");
+		return None;
+	};
+	let path = destination(flags, document, "image", "png")?;
+
+	match fs::write(&path, &image.0) {
+		Ok(()) => link(document, &path),
+		Err(e) => {
+			error!("Image could not be saved to {path:?}: {e}");
+			None
+		}
+	}
+}
+
+/// Copies or links to a dropped image, returning the Markdown linking to it
+pub fn dropped(flags: &Flags, document: Option<&Path>, image: &Path) -> Option<String> {
+	let Some(document) = document else {
+		// Without a document, there is nothing to copy the image next to
+		return link(Path::new(""), image);
+	};
+
+	if flags.drop_images() == Drop::Link {
+		return link(document, image);
+	}
+
+	let name = image.file_stem()?.to_string_lossy();
+	let extension = image.extension()?.to_string_lossy();
+	let path = destination(flags, document, &name, &extension)?;
+
+	match fs::copy(image, &path) {
+		Ok(_) => link(document, &path),
+		Err(e) => {
+			error!("Image {image:?} could not be copied to {path:?}: {e}");
+			None
+		}
+	}
+}
+
+/// Where a new image is placed, following the "image_path" template.
+///
+/// The template is relative to the document and may contain "{file}" (the name of the document),
+/// "{name}" (the name of the image) and "{n}" (the first number that is not taken).
+/// Without "{n}", a number is added to the name instead of replacing a file that already exists.
+fn destination(flags: &Flags, document: &Path, name: &str, extension: &str) -> Option<PathBuf> {
+	let Some(dir) = document.parent() else {
+		warn!("Document {document:?} has no parent directory");
+		return None;
+	};
+	let file = document.file_stem()?.to_string_lossy();
+
+	let expand = |n: usize| {
+		let path = flags
+			.image_path
+			.replace("{file}", &file)
+			.replace("{name}", name)
+			.replace("{n}", &n.to_string());
+		dir.join(path).with_extension(extension)
+	};
+
+	let path = if flags.image_path.contains("{n}") {
+		(1..).map(expand).find(|path| !path.exists())?
+	} else {
+		let path = expand(1);
+		let stem = path.file_stem()?.to_string_lossy().into_owned();
+		let numbered = (2..).map(|n| {
+			path.with_file_name(format!("{stem}-{n}"))
+				.with_extension(extension)
+		});
+		std::iter::once(path.clone())
+			.chain(numbered)
+			.find(|path| !path.exists())?
+	};
+
+	if let Some(parent) = path.parent() {
+		if let Err(e) = fs::create_dir_all(parent) {
+			error!("Image directory {parent:?} could not be created: {e}");
+			return None;
+		}
+	}
+
+	Some(path)
+}
+
+/// An image linking to the path, relative to the document if possible.
+///
+/// Paths containing spaces or brackets are wrapped in angle brackets, so that they are still read
+/// as a whole.
+fn link(document: &Path, path: &Path) -> Option<String> {
+	let alt = path.file_stem()?.to_string_lossy();
+
+	let path = document
+		.parent()
+		.and_then(|dir| relative(dir, path))
+		.unwrap_or_else(|| path.into());
+	let url = path
+		.components()
+		.map(|component| component.as_os_str().to_string_lossy())
+		.collect::<Vec<_>>()
+		.join("/")
+		// The root of absolute paths is a component of its own
+		.replacen("//", "/", 1);
+
+	if url.contains(|ch: char| ch.is_whitespace() || "()<>".contains(ch)) {
+		let url = url
+			.replace('\\', "\\\\")
+			.replace('<', "\\<")
+			.replace('>', "\\>");
+		Some(format!("![{alt}](<{url}>)"))
+	} else {
+		Some(format!("![{alt}]({url})"))
+	}
+}
+
+/// The path relative to the directory, if it is inside of it
+fn relative(dir: &Path, path: &Path) -> Option<PathBuf> {
+	let path = path.strip_prefix(dir).ok()?;
+	path.components()
+		.all(|component| matches!(component, Component::Normal(_)))
+		.then(|| path.into())
+}
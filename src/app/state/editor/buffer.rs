@@ -0,0 +1,192 @@
+//! Helpers for commands that edit the text directly, rather than through the editor widget
+
+use std::{
+	ops::{Range, RangeInclusive},
+	sync::Arc,
+};
+
+use cosmic::widget::text_editor::{Action, Edit, Motion};
+
+use crate::md::format::Change;
+
+use super::Editor;
+
+impl Editor {
+	/// The byte offset of the cursor within the text
+	pub(super) fn cursor_offset(&self) -> usize {
+		let (line, column) = self.text.cursor_position();
+		offset(&self.text.text(), line, column)
+	}
+
+	/// The byte range of the selected text, or an empty range at the cursor if nothing is selected
+	pub(super) fn selection_range(&mut self) -> Range<usize> {
+		let text = self.text.text();
+		let cursor = self.cursor_offset();
+
+		let Some(selection) = self.text.selection() else {
+			return cursor..cursor;
+		};
+
+		// The cursor is always on one end of the selection, but the text on both sides of it may
+		// match the selection, such as in "abab"
+		let start = cursor.saturating_sub(selection.len());
+		let before = text.get(start..cursor) == Some(selection.as_str());
+		let after = text.get(cursor..cursor + selection.len()) == Some(selection.as_str());
+
+		if before && (!after || self.selection_ends_at_cursor()) {
+			start..cursor
+		} else {
+			cursor..(cursor + selection.len()).min(text.len())
+		}
+	}
+
+	/// Checks if the selection ends at the cursor by selecting one more character, which grows
+	/// the selection only if it does, and then changing it back
+	fn selection_ends_at_cursor(&mut self) -> bool {
+		let len = |editor: &Self| {
+			editor
+				.text
+				.selection()
+				.map_or(0, |selection| selection.len())
+		};
+
+		let before = len(self);
+		self.text.perform(Action::Select(Motion::Right));
+		let grew = len(self) > before;
+		self.text.perform(Action::Select(Motion::Left));
+
+		grew
+	}
+
+	/// The lines containing the selection, or the line the cursor is on
+	pub(super) fn selected_lines(&mut self) -> RangeInclusive<usize> {
+		let text = self.text.text();
+		let range = self.selection_range();
+
+		let line = |offset: usize| text[..offset].matches('\n').count();
+		line(range.start)..=line(range.end)
+	}
+
+	/// Replaces the text, placing the cursor at the byte offset.
+	///
+	/// Only the part that differs is replaced, so that the editor keeps its scroll position.
+	pub(super) fn set_text(&mut self, text: &str, cursor: usize) {
+		// The content always adds a trailing newline when getting the text
+		let old = self.text.text();
+		let old = old.strip_suffix('\n').unwrap_or(&old);
+		let text = text.strip_suffix('\n').unwrap_or(text);
+
+		let prefix = common_prefix(old, text);
+		let suffix = common_suffix(&old[prefix..], &text[prefix..]);
+		let removed = &old[prefix..old.len() - suffix];
+		let inserted = &text[prefix..text.len() - suffix];
+
+		if !removed.is_empty() || !inserted.is_empty() {
+			self.move_to(prefix);
+			for _ in removed.chars() {
+				self.text.perform(Action::Select(Motion::Right));
+			}
+
+			let edit = if inserted.is_empty() {
+				Edit::Delete
+			} else {
+				Edit::Paste(Arc::new(inserted.into()))
+			};
+			self.text.perform(Action::Edit(edit));
+		}
+
+		self.move_to(cursor);
+	}
+
+	/// Moves the cursor to the byte offset, without changing the text
+	pub(super) fn move_to(&mut self, offset: usize) {
+		let text = self.text.text();
+		let offset = floor_char_boundary(&text, offset);
+		let cursor = self.cursor_offset();
+
+		// Moving left or right with a selection goes to its ends instead, so the cursor is only
+		// moved from where it is if nothing is selected and the offset is closer to it than to the
+		// start
+		let moves = if self.text.selection().is_some() {
+			None
+		} else if cursor <= offset {
+			Some((Motion::Right, &text[cursor..offset]))
+		} else if cursor - offset < offset {
+			Some((Motion::Left, &text[offset..cursor]))
+		} else {
+			None
+		};
+
+		let (motion, chars) = moves.unwrap_or_else(|| {
+			self.text.perform(Action::Move(Motion::DocumentStart));
+			(Motion::Right, &text[..offset])
+		});
+		for _ in chars.chars() {
+			self.text.perform(Action::Move(motion));
+		}
+	}
+
+	/// Replaces the text with the result of a formatting command, selecting the range it provides
+	pub(super) fn apply(&mut self, change: Change) {
+		self.set_text(&change.text, change.selection.end);
+
+		let selected = change.text.get(change.selection).unwrap_or_default();
+		for _ in selected.chars() {
+			self.text.perform(Action::Select(Motion::Left));
+		}
+	}
+}
+
+/// The byte offset of a column on a line
+pub fn offset(text: &str, line: usize, column: usize) -> usize {
+	let start = line_start(text, line);
+	let len = text[start..].find('\n').unwrap_or(text.len() - start);
+	start + column.min(len)
+}
+
+/// The line of a byte offset, along with the byte column within that line
+pub fn position(text: &str, offset: usize) -> (usize, usize) {
+	let before = &text[..offset.min(text.len())];
+	let start = before.rfind('\n').map_or(0, |i| i + 1);
+	(before.matches('\n').count(), before.len() - start)
+}
+
+/// The byte offset of the start of a line
+pub fn line_start(text: &str, line: usize) -> usize {
+	text.split_inclusive('\n')
+		.take(line)
+		.map(str::len)
+		.sum::<usize>()
+		.min(text.len())
+}
+
+/// The byte offset of the end of a line, before the newline
+pub fn line_end(text: &str, line: usize) -> usize {
+	offset(text, line, usize::MAX)
+}
+
+/// The length of the text two strings start with, in bytes
+fn common_prefix(a: &str, b: &str) -> usize {
+	a.char_indices()
+		.zip(b.chars())
+		.find(|((_, a), b)| a != b)
+		.map_or(a.len().min(b.len()), |((i, _), _)| i)
+}
+
+/// The length of the text two strings end with, in bytes
+fn common_suffix(a: &str, b: &str) -> usize {
+	a.chars()
+		.rev()
+		.zip(b.chars().rev())
+		.take_while(|(a, b)| a == b)
+		.map(|(a, _)| a.len_utf8())
+		.sum()
+}
+
+fn floor_char_boundary(text: &str, mut offset: usize) -> usize {
+	offset = offset.min(text.len());
+	while !text.is_char_boundary(offset) {
+		offset -= 1;
+	}
+	offset
+}
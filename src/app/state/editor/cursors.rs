@@ -0,0 +1,347 @@
+//! Extra cursors, which edit the text in the same way as the cursor of the editor widget.
+//!
+//! Each cursor is a byte range of selected text, with the cursor itself at the end of it.
+
+use std::ops::Range;
+
+use cosmic::{
+	iced::Point,
+	widget::text_editor::{Action, Edit, Motion},
+};
+
+use super::{Editor, buffer};
+
+/// Commands that add or remove extra cursors
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Command {
+	AddAbove,
+	AddBelow,
+	/// Selects the next occurrence of the selected text, or the word under the cursor
+	AddNextOccurrence,
+	/// Selects every occurrence of the selected text, or the word under the cursor
+	SelectAllOccurrences,
+	Clear,
+}
+
+/// An edit made at every cursor
+#[derive(Debug, Clone, PartialEq)]
+enum Input {
+	Text(String),
+	Backspace,
+	Delete,
+}
+
+impl Editor {
+	pub(super) fn cursor_command(&mut self, command: Command) {
+		let text = self.text.text();
+		let primary = self.selection_range();
+
+		match command {
+			Command::AddAbove | Command::AddBelow => {
+				let up = command == Command::AddAbove;
+				let cursors = self.cursors.iter().map(|cursor| cursor.end);
+				let Some(from) = cursors
+					.chain([self.cursor_offset()])
+					.reduce(|a, b| if up == (b < a) { b } else { a })
+				else {
+					return;
+				};
+
+				let (line, column) = line_column(&text, from);
+				let line = match up {
+					true if line == 0 => return,
+					true => line - 1,
+					false => line + 1,
+				};
+				if line >= text.split('\n').count() {
+					return;
+				}
+
+				let offset = char_offset(&text, line, column);
+				self.cursors.push(offset..offset);
+			}
+
+			Command::AddNextOccurrence | Command::SelectAllOccurrences if primary.is_empty() => {
+				self.text.perform(Action::SelectWord);
+			}
+
+			Command::AddNextOccurrence => {
+				let after = self.cursors.last().unwrap_or(&primary).end;
+				let occurrences = occurrences(&text, &text[primary.clone()]);
+
+				// Wraps around to the start of the text
+				let next = occurrences
+					.iter()
+					.filter(|range| range.start >= after)
+					.chain(occurrences.iter().filter(|range| range.start < after))
+					.find(|range| **range != primary && !self.cursors.contains(range));
+
+				self.cursors.extend(next.cloned());
+			}
+
+			Command::SelectAllOccurrences => {
+				self.cursors = occurrences(&text, &text[primary.clone()])
+					.into_iter()
+					.filter(|range| *range != primary)
+					.collect();
+			}
+
+			Command::Clear => self.cursors.clear(),
+		}
+
+		self.cursors.sort_by_key(|cursor| cursor.start);
+		self.cursors.dedup();
+	}
+
+	/// Performs an action at every cursor.
+	///
+	/// Returns false if the action should be performed by the editor widget instead, which removes
+	/// the extra cursors if they cannot follow it. Rectangles are only selected if extra cursors
+	/// can be shown.
+	pub(super) fn perform_at_cursors(&mut self, action: &Action, shown: bool) -> bool {
+		if let Action::Click(point) | Action::Drag(point) = action {
+			if !shown {
+				self.cursors.clear();
+				self.column_anchor = None;
+				return false;
+			}
+			return self.column_select(action, *point);
+		}
+		self.column_anchor = None;
+
+		if self.cursors.is_empty() {
+			return false;
+		}
+
+		let input = match action {
+			Action::Edit(Edit::Insert(ch)) => Input::Text(ch.to_string()),
+			Action::Edit(Edit::Paste(text)) => Input::Text(text.to_string()),
+			Action::Edit(Edit::Enter) => Input::Text("\n".into()),
+			Action::Edit(Edit::Backspace) => Input::Backspace,
+			Action::Edit(Edit::Delete) => Input::Delete,
+
+			Action::Move(motion) => {
+				let text = self.text.text();
+				match self
+					.cursors
+					.iter()
+					.map(|cursor| shift(&text, cursor.clone(), *motion))
+					.collect::<Option<Vec<usize>>>()
+				{
+					Some(cursors) => {
+						self.cursors = cursors.into_iter().map(|cursor| cursor..cursor).collect();
+						self.cursors.dedup();
+					}
+					None => self.cursors.clear(),
+				}
+				return false;
+			}
+
+			Action::Scroll { .. } => return false,
+
+			_ => {
+				self.cursors.clear();
+				return false;
+			}
+		};
+
+		let mut selections = self.cursors.clone();
+		selections.push(self.selection_range());
+
+		let (text, carets, primary) = edit(&self.text.text(), selections, &input);
+		self.set_text(&text, primary);
+		self.cursors = carets
+			.into_iter()
+			.filter(|caret| *caret != primary)
+			.map(|caret| caret..caret)
+			.collect();
+
+		true
+	}
+
+	/// Selects a rectangle of text when dragging the mouse while holding Alt.
+	///
+	/// Every line of the rectangle but the one the mouse is on gets an extra cursor.
+	fn column_select(&mut self, action: &Action, point: Point) -> bool {
+		self.cursors.clear();
+
+		if !self.modifiers.alt() {
+			self.column_anchor = None;
+			return false;
+		}
+
+		let Action::Drag(_) = action else {
+			self.text.perform(action.clone());
+			self.column_anchor = Some(self.text.cursor_position());
+			return true;
+		};
+		let Some((anchor_line, anchor_column)) = self.column_anchor else {
+			return false;
+		};
+
+		// Clicking again places the cursor under the mouse without selecting anything
+		self.text.perform(Action::Click(point));
+
+		let text = self.text.text();
+		let (line, column) = line_column(&text, self.cursor_offset());
+		let anchor_column = text
+			.split('\n')
+			.nth(anchor_line)
+			.map(|line| line.get(..anchor_column).unwrap_or(line).chars().count())
+			.unwrap_or_default();
+
+		let columns = anchor_column.min(column)..anchor_column.max(column);
+		let row = |line: usize| {
+			let start = char_offset(&text, line, columns.start);
+			let end = char_offset(&text, line, columns.end);
+			if column < anchor_column {
+				end..start
+			} else {
+				start..end
+			}
+		};
+
+		for other in anchor_line.min(line)..=anchor_line.max(line) {
+			if other == line {
+				continue;
+			}
+
+			let range = row(other);
+			self.cursors
+				.push(range.start.min(range.end)..range.start.max(range.end));
+		}
+
+		// The cursor of the editor widget selects its own line of the rectangle
+		let range = row(line);
+		let motion = if range.start < range.end {
+			Motion::Left
+		} else {
+			Motion::Right
+		};
+		for _ in text[range.start.min(range.end)..range.start.max(range.end)].chars() {
+			self.text.perform(Action::Select(motion));
+		}
+
+		true
+	}
+}
+
+/// Replaces or deletes the selections, returning the new text, the new offset of every cursor,
+/// and the new offset of the last cursor
+fn edit(
+	text: &str,
+	mut selections: Vec<Range<usize>>,
+	input: &Input,
+) -> (String, Vec<usize>, usize) {
+	for selection in &mut selections {
+		match input {
+			Input::Backspace if selection.start == selection.end => {
+				selection.start = previous(text, selection.start)
+			}
+			Input::Delete if selection.start == selection.end => {
+				selection.end = next(text, selection.end)
+			}
+			_ => (),
+		}
+	}
+
+	let replacement = match input {
+		Input::Text(text) => text.as_str(),
+		Input::Backspace | Input::Delete => "",
+	};
+
+	let mut order = (0..selections.len()).collect::<Vec<usize>>();
+	order.sort_by_key(|i| selections[*i].start);
+
+	let mut result = String::with_capacity(text.len());
+	let mut carets = vec![0; selections.len()];
+	let mut end = 0;
+	let mut start = None;
+
+	for i in order {
+		let selection = &selections[i];
+
+		// Overlapping cursors are merged into the one before them
+		if selection.start < end || start == Some(selection.start) {
+			carets[i] = result.len();
+			end = end.max(selection.end);
+			continue;
+		}
+
+		result.push_str(&text[end..selection.start]);
+		result.push_str(replacement);
+		carets[i] = result.len();
+		end = selection.end;
+		start = Some(selection.start);
+	}
+	result.push_str(&text[end..]);
+
+	let primary = carets[carets.len() - 1];
+	carets.sort();
+	carets.dedup();
+
+	(result, carets, primary)
+}
+
+/// Where a cursor ends up after a motion, or None if extra cursors cannot follow the motion
+fn shift(text: &str, cursor: Range<usize>, motion: Motion) -> Option<usize> {
+	let (line, column) = line_column(text, cursor.end);
+	let lines = text.split('\n').count();
+
+	Some(match motion {
+		Motion::Left if !cursor.is_empty() => cursor.start,
+		Motion::Right if !cursor.is_empty() => cursor.end,
+		Motion::Left => previous(text, cursor.end),
+		Motion::Right => next(text, cursor.end),
+		Motion::Up if line == 0 => 0,
+		Motion::Up => char_offset(text, line - 1, column),
+		Motion::Down if line + 1 >= lines => text.len(),
+		Motion::Down => char_offset(text, line + 1, column),
+		Motion::Home => char_offset(text, line, 0),
+		Motion::End => char_offset(text, line, usize::MAX),
+		_ => return None,
+	})
+}
+
+fn occurrences(text: &str, needle: &str) -> Vec<Range<usize>> {
+	text.match_indices(needle)
+		.map(|(start, _)| start..start + needle.len())
+		.collect()
+}
+
+/// The line of a byte offset, along with the amount of characters before it in the line
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+	let before = &text[..offset];
+	let start = before.rfind('\n').map_or(0, |i| i + 1);
+	(
+		before.matches('\n').count(),
+		before[start..].chars().count(),
+	)
+}
+
+/// The byte offset of a character within a line, which is moved to the end of the line if it is
+/// too short
+fn char_offset(text: &str, line: usize, column: usize) -> usize {
+	let start = buffer::line_start(text, line);
+	let content = text[start..].split('\n').next().unwrap_or_default();
+
+	start
+		+ content
+			.char_indices()
+			.nth(column)
+			.map_or(content.len(), |(i, _)| i)
+}
+
+fn previous(text: &str, offset: usize) -> usize {
+	text[..offset]
+		.char_indices()
+		.next_back()
+		.map_or(0, |(i, _)| i)
+}
+
+fn next(text: &str, offset: usize) -> usize {
+	text[offset..]
+		.chars()
+		.next()
+		.map_or(offset, |ch| offset + ch.len_utf8())
+}
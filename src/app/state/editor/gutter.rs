@@ -0,0 +1,317 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use cosmic::{
+	Element,
+	iced::{Color, Font, Length, alignment::Horizontal, font::Weight, widget::text::LineHeight},
+	iced_widget::{Row, column, rich_text, row, text},
+	theme,
+	widget::{container, horizontal_space, vertical_space},
+};
+
+use crate::{app::message::Message, utils::cfg::flags::Flags};
+
+/// The padding around the text of the editor
+pub const PADDING: f32 = 10.;
+
+/// The thickness of the line under problems
+const UNDERLINE: f32 = 2.;
+
+/// The width of extra cursors
+const CURSOR: f32 = 2.;
+
+/// How line numbers are shown in the gutter
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LineNumbers {
+	None,
+	Absolute,
+	/// Distance from the line the cursor is on
+	Relative,
+}
+
+/// How text in the editor is wrapped
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Wrap {
+	None,
+	/// Wraps at the edge of the editor
+	Window,
+	/// Wraps after the given amount of characters
+	Column(usize),
+}
+
+/// How text is underlined
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Underline {
+	/// Used for problems found by the linter
+	Straight,
+	/// Used for misspelled words
+	Squiggly,
+	/// Used for wiki links to other notes
+	Link,
+}
+
+/// Something on a line worth pointing out
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Marker {
+	/// The line contains an occurrence of the selected text
+	Occurrence,
+	/// The linter found a problem on the line
+	Problem,
+}
+
+/// The layout of the text in the editor, used to line things up with it.
+///
+/// Only used while text is not wrapped, since where the editor wraps lines is not known, and the
+/// editor uses a monospace font, since the text itself cannot be measured.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+	pub size: f32,
+	pub line_height: f32,
+	line_count: usize,
+	/// The amount of columns of the longest line
+	longest: usize,
+}
+
+impl Metrics {
+	pub fn new(size: f32, line_height: LineHeight, line_count: usize, longest: usize) -> Self {
+		Self {
+			size,
+			line_height: line_height.to_absolute(size.into()).0,
+			line_count,
+			longest,
+		}
+	}
+
+	/// The approximate width of a single character
+	pub fn char_width(size: f32) -> f32 {
+		size * 0.6
+	}
+
+	/// The width needed to fit the longest line
+	pub fn text_width(&self) -> f32 {
+		self.columns_width(self.longest)
+	}
+
+	/// The width needed to fit the given amount of columns
+	pub fn columns_width(&self, columns: usize) -> f32 {
+		PADDING * 2. + columns as f32 * Self::char_width(self.size)
+	}
+
+	/// The distance from the top of the editor to the top of the line
+	pub fn line_y(&self, line: usize) -> f32 {
+		PADDING + self.line_height * line.min(self.line_count) as f32
+	}
+
+	pub fn line_count(&self) -> usize {
+		self.line_count
+	}
+}
+
+/// The amount of columns of the longest line of the text, counting tabs as multiple columns
+pub fn longest_line(text: &str, tab_len: usize) -> usize {
+	text.split('\n')
+		.map(|line| {
+			line.chars()
+				.map(|ch| if ch == '\t' { tab_len } else { 1 })
+				.sum()
+		})
+		.max()
+		.unwrap_or_default()
+}
+
+impl Marker {
+	fn color(&self, flags: &Flags) -> Color {
+		match self {
+			Self::Occurrence => flags.palette.primary,
+			Self::Problem => flags.palette.danger,
+		}
+	}
+}
+
+/// Renders the line numbers and markers next to the editor
+pub fn view<'a>(
+	flags: &Flags,
+	metrics: &Metrics,
+	cursor_line: usize,
+	markers: &BTreeMap<usize, Marker>,
+) -> Element<'a, Message> {
+	let line_height = LineHeight::Absolute(metrics.line_height.into());
+	let mut gutter = row![].spacing(spacing(metrics.size));
+
+	if flags.markers {
+		let spans = (0..metrics.line_count())
+			.map(|line| match markers.get(&line) {
+				Some(marker) => text::Span::new("●\n").color(marker.color(flags)),
+				None => text::Span::new("\n"),
+			})
+			.collect::<Vec<text::Span<'a, Message>>>();
+
+		gutter = gutter.push(
+			rich_text(spans)
+				.size(metrics.size)
+				.line_height(line_height)
+				.width(metrics.size),
+		);
+	}
+
+	let line_numbers = flags.line_numbers();
+	if line_numbers != LineNumbers::None {
+		let spans = (0..metrics.line_count())
+			.map(|line| {
+				let number = match line_numbers {
+					LineNumbers::Relative if line != cursor_line => line.abs_diff(cursor_line),
+					_ => line + 1,
+				};
+
+				let span = text::Span::new(format!("{number}\n"));
+				if line == cursor_line {
+					span.font(Font {
+						weight: Weight::Bold,
+						..Font::MONOSPACE
+					})
+				} else {
+					span.font(Font::MONOSPACE)
+						.color(flags.palette.text.scale_alpha(0.5))
+				}
+			})
+			.collect::<Vec<text::Span<'a, Message>>>();
+
+		gutter = gutter.push(
+			rich_text(spans)
+				.size(metrics.size)
+				.line_height(line_height)
+				.width(numbers_width(metrics.size, metrics.line_count()))
+				.align_x(Horizontal::Right),
+		);
+	}
+
+	container(gutter).padding([PADDING, 0.]).into()
+}
+
+/// Wide enough to fit the longest line number
+fn numbers_width(size: f32, line_count: usize) -> f32 {
+	let digits = line_count.max(1).ilog10() + 1;
+	digits as f32 * Metrics::char_width(size)
+}
+
+fn spacing(size: f32) -> f32 {
+	size / 2.
+}
+
+/// Renders a highlight over the line the cursor is on
+pub fn highlight<'a>(flags: &Flags, metrics: &Metrics, cursor_line: usize) -> Element<'a, Message> {
+	column![
+		vertical_space().height(metrics.line_y(cursor_line)),
+		container(horizontal_space())
+			.width(Length::Fill)
+			.height(metrics.line_height)
+			.class(background(flags.palette.primary.scale_alpha(0.1))),
+	]
+	.into()
+}
+
+/// Renders a line under the columns of a line.
+///
+/// Tabs count as multiple columns, like they do in [longest_line].
+pub fn underline<'a>(
+	flags: &Flags,
+	metrics: &Metrics,
+	line: usize,
+	columns: Range<usize>,
+	style: Underline,
+) -> Element<'a, Message> {
+	let bottom = metrics.line_y(line + 1);
+	let width = columns.len().max(1) as f32 * Metrics::char_width(metrics.size);
+
+	let line: Element<'a, Message> = match style {
+		Underline::Straight => container(horizontal_space())
+			.width(width)
+			.height(UNDERLINE)
+			.class(background(flags.palette.danger))
+			.into(),
+
+		Underline::Link => container(horizontal_space())
+			.width(width)
+			.height(UNDERLINE)
+			.class(background(flags.palette.primary))
+			.into(),
+
+		// Segments alternate between being raised and lowered
+		Underline::Squiggly => {
+			let segment = Metrics::char_width(metrics.size) / 2.;
+			let segments = (width / segment).ceil() as usize;
+
+			Row::with_children((0..segments).map(|i| {
+				column![
+					vertical_space().height(if i % 2 == 0 { 0. } else { UNDERLINE }),
+					container(horizontal_space())
+						.width(segment)
+						.height(UNDERLINE)
+						.class(background(flags.palette.danger)),
+				]
+				.into()
+			}))
+			.into()
+		}
+	};
+
+	column![
+		vertical_space().height(bottom - UNDERLINE * 2.),
+		row![
+			horizontal_space()
+				.width(PADDING + columns.start as f32 * Metrics::char_width(metrics.size)),
+			line,
+		],
+	]
+	.into()
+}
+
+/// Renders an extra cursor, highlighting the columns it selects
+pub fn cursor<'a>(
+	flags: &Flags,
+	metrics: &Metrics,
+	line: usize,
+	columns: Range<usize>,
+) -> Element<'a, Message> {
+	let len = columns.len();
+
+	let cursor = if len == 0 {
+		container(horizontal_space())
+			.width(CURSOR)
+			.height(metrics.line_height)
+			.class(background(flags.palette.text))
+	} else {
+		container(horizontal_space())
+			.width(len as f32 * Metrics::char_width(metrics.size))
+			.height(metrics.line_height)
+			.class(background(flags.palette.primary.scale_alpha(0.3)))
+	};
+
+	column![
+		vertical_space().height(metrics.line_y(line)),
+		row![
+			horizontal_space()
+				.width(PADDING + columns.start as f32 * Metrics::char_width(metrics.size)),
+			cursor,
+		],
+	]
+	.into()
+}
+
+/// Renders a vertical line after the given column
+pub fn ruler<'a>(flags: &Flags, metrics: &Metrics, column: usize) -> Element<'a, Message> {
+	row![
+		horizontal_space().width(metrics.columns_width(column) - PADDING),
+		container(vertical_space())
+			.width(1.)
+			.height(Length::Fill)
+			.class(background(flags.palette.text.scale_alpha(0.25))),
+	]
+	.into()
+}
+
+fn background<'a>(color: Color) -> theme::Container<'a> {
+	theme::Container::custom(move |_| container::Style {
+		background: Some(color.into()),
+		..Default::default()
+	})
+}
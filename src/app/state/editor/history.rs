@@ -0,0 +1,282 @@
+//! The undo and redo stacks of the editor, which can be saved between sessions
+
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use cosmic::{
+	iced::Task,
+	widget::text_editor::{Action, Edit},
+};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::utils::cfg::{deserialize_or_default, get_or_create_cfg_dir, serialize_or_log};
+
+/// The directory within the config dir that the history of each file is saved in
+const DIR: &str = ".history";
+
+/// The text and cursor at one point of the history
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+	/// Shared with the analyses of the text, rather than copied
+	pub text: Arc<str>,
+	/// The byte offset of the cursor
+	pub cursor: usize,
+}
+
+/// A step of the history, which replaces the text that changed instead of keeping all of it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Change {
+	/// The byte offset the replaced text starts at
+	start: usize,
+	/// The length in bytes of the replaced text
+	len: usize,
+	replacement: String,
+	/// The byte offset of the cursor afterwards
+	cursor: usize,
+}
+
+/// Kinds of edits that are undone together when done one after another
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Group {
+	/// Typing the characters of a word
+	Word,
+	/// Typing whitespace, which is grouped with the word before it
+	Space,
+	/// Deleting characters with backspace or delete
+	Delete,
+}
+
+/// What the file of a document holds, starting with its path so that it can be read on its own
+#[derive(Deserialize, Default)]
+struct Saved {
+	path: PathBuf,
+	history: History,
+}
+
+#[derive(Deserialize, Default)]
+struct Header {
+	path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct History {
+	/// The changes turning the current text into the state before each step
+	undo: Vec<Change>,
+	/// The changes turning the current text into the state after each undone step
+	redo: Vec<Change>,
+	/// The state the last change left the text in
+	current: Snapshot,
+	/// The group of the last change, which following changes are merged into
+	#[serde(skip)]
+	last: Option<Group>,
+	/// The group of the change that is about to be recorded
+	#[serde(skip)]
+	next: Option<Group>,
+}
+
+impl Group {
+	/// The group of an action of the editor widget.
+	///
+	/// Returns None for edits that are undone on their own, such as pasting.
+	pub fn of(action: &Action) -> Option<Self> {
+		match action {
+			Action::Edit(Edit::Insert(ch)) if ch.is_whitespace() => Some(Self::Space),
+			Action::Edit(Edit::Insert(_)) => Some(Self::Word),
+			Action::Edit(Edit::Backspace | Edit::Delete) => Some(Self::Delete),
+			_ => None,
+		}
+	}
+
+	/// Whether an edit of the group continues an edit of the other group
+	fn follows(self, other: Self) -> bool {
+		self == other || (self == Self::Space && other == Self::Word)
+	}
+}
+
+impl Change {
+	/// The change turning the text into the snapshot, replacing what is between the start and
+	/// end they share
+	fn between(text: &str, snapshot: &Snapshot) -> Self {
+		let start = text
+			.chars()
+			.zip(snapshot.text.chars())
+			.take_while(|(a, b)| a == b)
+			.map(|(ch, _)| ch.len_utf8())
+			.sum::<usize>();
+		let end = text[start..]
+			.chars()
+			.rev()
+			.zip(snapshot.text[start..].chars().rev())
+			.take_while(|(a, b)| a == b)
+			.map(|(ch, _)| ch.len_utf8())
+			.sum::<usize>();
+
+		Self {
+			start,
+			len: text.len() - start - end,
+			replacement: snapshot.text[start..snapshot.text.len() - end].into(),
+			cursor: snapshot.cursor,
+		}
+	}
+
+	/// Applies the change to the snapshot, returning the change that reverts it, or None if it
+	/// does not fit the text
+	fn apply(self, snapshot: &mut Snapshot) -> Option<Self> {
+		let replaced = snapshot.text.get(self.start..self.start + self.len)?;
+		let revert = Self {
+			start: self.start,
+			len: self.replacement.len(),
+			replacement: replaced.into(),
+			cursor: snapshot.cursor,
+		};
+
+		let text = &snapshot.text;
+		snapshot.text = [
+			&text[..self.start],
+			self.replacement.as_str(),
+			&text[self.start + self.len..],
+		]
+		.concat()
+		.into();
+		snapshot.cursor = self.cursor;
+
+		Some(revert)
+	}
+}
+
+impl History {
+	pub fn new(current: Snapshot) -> Self {
+		Self {
+			current,
+			..Self::default()
+		}
+	}
+
+	/// Reads the saved history of a file, which is only used if it ends in the current text
+	pub fn read(path: &Path, current: Snapshot) -> Self {
+		let file = file(&canonical(path));
+		if !file.exists() {
+			return Self::new(current);
+		}
+
+		let saved: Saved = deserialize_or_default(file);
+		if saved.history.current.text == current.text {
+			saved.history
+		} else {
+			Self::new(current)
+		}
+	}
+
+	pub fn write(&self, path: &Path) {
+		let path = canonical(path);
+		let file = file(&path);
+		if let Some(dir) = file.parent() {
+			if let Err(e) = fs::create_dir_all(dir) {
+				error!("History dir could not be created: {e}");
+				return;
+			}
+		}
+
+		serialize_or_log(file, &(path, self));
+	}
+
+	/// Sets the group of the next change, or stops merging changes if it is None
+	pub fn group(&mut self, group: Option<Group>) {
+		self.next = group;
+		if group.is_none() {
+			self.last = None;
+		}
+	}
+
+	/// Records the state of the text after a change, dropping the oldest steps over the limit.
+	///
+	/// Changes in the same group as the last one are merged into its step.
+	pub fn record(&mut self, snapshot: Snapshot, limit: usize) {
+		let next = self.next.take();
+		if snapshot.text == self.current.text {
+			self.current.cursor = snapshot.cursor;
+			return;
+		}
+
+		let merge = next
+			.zip(self.last)
+			.is_some_and(|(next, last)| next.follows(last));
+
+		// A merged step goes back to the state before the step it is merged into
+		let mut previous = self.current.clone();
+		if merge {
+			if let Some(change) = self.undo.pop() {
+				change.apply(&mut previous);
+			}
+		}
+
+		self.undo.push(Change::between(&snapshot.text, &previous));
+		if self.undo.len() > limit {
+			self.undo.drain(..self.undo.len() - limit);
+		}
+
+		self.current = snapshot;
+		self.redo.clear();
+		self.last = next;
+	}
+
+	/// Returns the state before the last step, if there is one
+	pub fn undo(&mut self) -> Option<Snapshot> {
+		let change = self.undo.pop()?.apply(&mut self.current)?;
+		self.redo.push(change);
+		self.last = None;
+		Some(self.current.clone())
+	}
+
+	/// Returns the state after the last undone step, if there is one
+	pub fn redo(&mut self) -> Option<Snapshot> {
+		let change = self.redo.pop()?.apply(&mut self.current)?;
+		self.undo.push(change);
+		self.last = None;
+		Some(self.current.clone())
+	}
+}
+
+/// Removes the saved history of documents that no longer exist
+pub fn prune<M: Send + 'static>() -> Task<M> {
+	Task::future(tokio::task::spawn_blocking(|| {
+		let Ok(entries) = fs::read_dir(get_or_create_cfg_dir().join(DIR)) else {
+			return;
+		};
+
+		for file in entries.flatten().map(|entry| entry.path()) {
+			// Files that cannot be read have no path, and are removed as well
+			let header: Header = deserialize_or_default(&file);
+			if !header.path.exists() {
+				if let Err(e) = fs::remove_file(&file) {
+					error!("History of {:?} could not be removed: {e}", header.path);
+				}
+			}
+		}
+	}))
+	.discard()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+	fs::canonicalize(path).unwrap_or_else(|_| path.into())
+}
+
+/// The file the history of a file is saved in, named after the FNV-1a hash of its path, which
+/// stays the same between versions of the program
+fn file(path: &Path) -> PathBuf {
+	let hash = path
+		.as_os_str()
+		.as_encoded_bytes()
+		.iter()
+		.fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+			(hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+		});
+
+	get_or_create_cfg_dir()
+		.join(DIR)
+		.join(format!("{hash:016x}"))
+}
@@ -0,0 +1,251 @@
+//! Key bindings that make writing Markdown more convenient
+
+use std::iter;
+
+use cosmic::{
+	iced::keyboard::{self, key::Named},
+	widget::text_editor::{self, Binding, Motion},
+};
+
+use crate::{
+	app::message::Message,
+	md::{self, block::Kind, list::Enter, pairs::Typed, table::Command},
+	utils::cfg::script::ScriptCfg,
+};
+
+use super::{Editor, TAB, cursors};
+
+impl Editor {
+	pub(super) fn key_bindings(
+		&self,
+		kp: text_editor::KeyPress,
+		cfg: &ScriptCfg,
+	) -> Option<Binding<Message>> {
+		// TODO: Custom bindings; Vim/Helix motions
+
+		if kp.status != text_editor::Status::Focused {
+			return None;
+		}
+
+		if let Some(binding) = self.auto_pair(&kp, cfg) {
+			return Some(binding);
+		}
+
+		match kp.key {
+			// Cursors
+			keyboard::Key::Named(Named::Escape) if !self.cursors.is_empty() => {
+				Some(Binding::Custom(Message::Cursors(cursors::Command::Clear)))
+			}
+			keyboard::Key::Named(Named::ArrowUp)
+				if kp.modifiers.command() && kp.modifiers.alt() =>
+			{
+				Some(Binding::Custom(Message::Cursors(
+					cursors::Command::AddAbove,
+				)))
+			}
+			keyboard::Key::Named(Named::ArrowDown)
+				if kp.modifiers.command() && kp.modifiers.alt() =>
+			{
+				Some(Binding::Custom(Message::Cursors(
+					cursors::Command::AddBelow,
+				)))
+			}
+
+			// Pairs
+			keyboard::Key::Named(Named::Backspace) if !kp.modifiers.command() => self
+				.delete_pair(cfg)
+				.or_else(|| Binding::from_key_press(kp)),
+
+			// Tables
+			keyboard::Key::Named(Named::Tab) if self.in_table() => {
+				let command = if kp.modifiers.shift() {
+					Command::PreviousCell
+				} else {
+					Command::NextCell
+				};
+				Some(Binding::Custom(Message::Table(command)))
+			}
+			keyboard::Key::Named(Named::Enter) if !kp.modifiers.shift() && self.in_table() => {
+				Some(Binding::Custom(Message::Table(Command::NextRow)))
+			}
+
+			// Lists
+			keyboard::Key::Named(Named::Tab) if kp.modifiers.shift() => self.outdent(cfg),
+			keyboard::Key::Named(Named::Tab) => Some(self.indent(cfg).unwrap_or_else(|| tab(cfg))),
+			keyboard::Key::Named(Named::Enter) if !kp.modifiers.shift() => self
+				.continue_block()
+				.or_else(|| Binding::from_key_press(kp)),
+
+			// Pasting is handled by the editor, so that it can turn values into a table and save
+			// images
+			keyboard::Key::Character(ref ch)
+				if ch.as_str() == "v" && kp.modifiers.command() && !kp.modifiers.alt() =>
+			{
+				Some(Binding::Custom(Message::Paste))
+			}
+
+			// Default bindings
+			_ => Binding::from_key_press(kp),
+		}
+	}
+
+	/// The line the cursor is on and the column within it.
+	///
+	/// Only returned if nothing is selected and the line is not part of a code block or table.
+	fn prose_line(&self) -> Option<(String, usize)> {
+		if self.text.selection().is_some() {
+			return None;
+		}
+
+		let (line, column) = self.text.cursor_position();
+		if self.blocks.get(line) != Some(&Kind::Prose) {
+			return None;
+		}
+
+		let current = self.text.line(line)?.to_string();
+		Some((current, column))
+	}
+
+	/// Checks if the cursor is in a table, with nothing selected
+	fn in_table(&self) -> bool {
+		let (line, _) = self.text.cursor_position();
+
+		self.text.selection().is_none() && self.blocks.get(line) == Some(&Kind::Table)
+	}
+
+	/// Checks if the cursor is in a code block, where characters are not typed in pairs
+	fn in_code(&self) -> bool {
+		let (line, _) = self.text.cursor_position();
+
+		matches!(self.blocks.get(line), Some(Kind::Code | Kind::Fence))
+	}
+
+	/// Continues the list or blockquote the cursor is in onto a new line
+	fn continue_block(&self) -> Option<Binding<Message>> {
+		let (line, column) = self.prose_line()?;
+
+		let bindings = match md::list::enter(&line, column)? {
+			Enter::Continue(prefix) => iter::once(Binding::Enter)
+				.chain(prefix.chars().map(Binding::Insert))
+				.collect(),
+
+			Enter::End(prefix) => {
+				let before = line[..column].chars().count();
+
+				iter::repeat_n(Binding::Select(Motion::Left), before)
+					.chain(iter::once(Binding::Backspace))
+					.chain(prefix.chars().map(Binding::Insert))
+					.collect()
+			}
+		};
+
+		Some(Binding::Sequence(bindings))
+	}
+
+	/// Indents the list item the cursor is on, keeping the cursor in place
+	fn indent(&self, cfg: &ScriptCfg) -> Option<Binding<Message>> {
+		let (line, column) = self.prose_line()?;
+		if !md::list::is_item(&line) {
+			return None;
+		}
+
+		let distance = line
+			.get(md::list::indent_start(&line)..column)?
+			.chars()
+			.count();
+
+		Some(Binding::Sequence(
+			iter::repeat_n(Binding::Move(Motion::Left), distance)
+				.chain(iter::once(tab(cfg)))
+				.chain(iter::repeat_n(Binding::Move(Motion::Right), distance))
+				.collect(),
+		))
+	}
+
+	/// Outdents the line the cursor is on by a single level, keeping the cursor in place
+	fn outdent(&self, cfg: &ScriptCfg) -> Option<Binding<Message>> {
+		let (line, column) = self.prose_line()?;
+
+		let len = md::list::outdent_len(&line, cfg.flags.tab_len());
+		if len == 0 {
+			return None;
+		}
+
+		let distance = line
+			.get(md::list::indent_start(&line)..column)?
+			.chars()
+			.count();
+
+		Some(Binding::Sequence(
+			iter::repeat_n(Binding::Move(Motion::Left), distance)
+				.chain(iter::repeat_n(Binding::Delete, len))
+				.chain(iter::repeat_n(
+					Binding::Move(Motion::Right),
+					distance.saturating_sub(len),
+				))
+				.collect(),
+		))
+	}
+
+	/// The text of the line the cursor is on, split at the cursor
+	fn around_cursor(&self) -> Option<(String, String)> {
+		let (line, column) = self.text.cursor_position();
+		let current = self.text.line(line)?.to_string();
+
+		let (before, after) = current.split_at(column.min(current.len()));
+		Some((before.into(), after.into()))
+	}
+
+	/// Surrounds the selection with a pair of characters, inserts a pair, or skips over the
+	/// character closing one
+	fn auto_pair(&self, kp: &text_editor::KeyPress, cfg: &ScriptCfg) -> Option<Binding<Message>> {
+		if kp.modifiers.command() || kp.modifiers.alt() || self.in_code() {
+			return None;
+		}
+
+		let ch = kp.text.as_ref()?.chars().next()?;
+		let pairs = &cfg.auto_pairs;
+
+		if self.text.selection().is_some() {
+			let close = pairs.get(&ch)?;
+			return Some(Binding::Custom(Message::Surround(
+				ch.to_string(),
+				close.to_string(),
+			)));
+		}
+
+		let (before, after) = self.around_cursor()?;
+
+		let binding = match md::pairs::typed(ch, &before, &after, pairs)? {
+			Typed::Pair(open, close) => Binding::Sequence(vec![
+				Binding::Insert(open),
+				Binding::Insert(close),
+				Binding::Move(Motion::Left),
+			]),
+			Typed::Skip => Binding::Move(Motion::Right),
+		};
+
+		Some(binding)
+	}
+
+	/// Deletes both characters of an empty pair at once
+	fn delete_pair(&self, cfg: &ScriptCfg) -> Option<Binding<Message>> {
+		if self.text.selection().is_some() || self.in_code() {
+			return None;
+		}
+
+		let (before, after) = self.around_cursor()?;
+
+		md::pairs::is_empty_pair(&before, &after, &cfg.auto_pairs)
+			.then(|| Binding::Sequence(vec![Binding::Backspace, Binding::Delete]))
+	}
+}
+
+/// The binding used to insert a tab
+fn tab(flags: &ScriptCfg) -> Binding<Message> {
+	if flags.flags.expand_tabs {
+		Binding::Sequence(vec![Binding::Insert(' '); flags.flags.tab_len()])
+	} else {
+		Binding::Insert(TAB)
+	}
+}
@@ -0,0 +1,104 @@
+//! The metadata panel, listing the fields of the front matter
+
+use cosmic::{
+	Element,
+	iced::{Font, Length, font::Weight},
+	iced_widget::{Column, column, scrollable, text},
+};
+use rhai::{Array, Dynamic, Map};
+use tracing::warn;
+
+use crate::{
+	app::message::Message,
+	md::front_matter::{FrontMatter, Value},
+	trans,
+	utils::cfg::script::ScriptCfg,
+};
+
+/// The fields shown before the others, in order
+const FIRST: [&str; 3] = ["title", "tags", "date"];
+
+/// The fields shown in the metadata panel, after passing the front matter through the
+/// "front_matter_hook" flag
+pub fn fields(cfg: &ScriptCfg, front_matter: &FrontMatter) -> Vec<(String, String)> {
+	let mut map = to_map(front_matter);
+
+	let hook = &cfg.flags.front_matter_hook;
+	if cfg.has_rhai_fn(hook) {
+		match cfg.call_rhai_fn::<Map>(hook.clone(), (map.clone(),)) {
+			Ok(new) => map = new,
+			Err(e) => warn!("Front matter hook failed: {e}"),
+		}
+	}
+
+	// The map is sorted, so the rest of the fields are in alphabetical order
+	let mut fields = map
+		.into_iter()
+		.map(|(key, value)| (key.to_string(), display(value)))
+		.collect::<Vec<(String, String)>>();
+	fields.sort_by_key(|(key, _)| {
+		FIRST
+			.iter()
+			.position(|first| key == first)
+			.unwrap_or(FIRST.len())
+	});
+
+	fields
+}
+
+/// Lists the fields of the front matter
+pub fn view<'a>(fields: &[(String, String)], size: f32) -> Element<'a, Message> {
+	if fields.is_empty() {
+		return text(trans!("no_metadata")).size(size).into();
+	}
+
+	let fields = fields
+		.iter()
+		.map(|(key, value)| {
+			column![
+				text(key.clone()).size(size * 0.8).font(Font {
+					weight: Weight::Bold,
+					..Font::DEFAULT
+				}),
+				text(value.clone()).size(size),
+			]
+			.into()
+		})
+		.collect::<Vec<Element<'a, Message>>>();
+
+	scrollable(Column::with_children(fields).spacing(size / 2.))
+		.width(size * 15.)
+		.height(Length::Fill)
+		.into()
+}
+
+/// The front matter as it is passed to Rhai, with lists as arrays
+pub fn to_map(front_matter: &FrontMatter) -> Map {
+	front_matter
+		.fields
+		.iter()
+		.map(|(key, value)| {
+			let value = match value {
+				Value::Text(text) => Dynamic::from(text.clone()),
+				Value::List(items) => {
+					Dynamic::from_array(items.iter().cloned().map(Dynamic::from).collect::<Array>())
+				}
+			};
+			(key.into(), value)
+		})
+		.collect()
+}
+
+fn display(value: Dynamic) -> String {
+	if value.is_array() {
+		value
+			.into_array()
+			.unwrap_or_default()
+			.iter()
+			.map(Dynamic::to_string)
+			.collect::<Vec<String>>()
+			.join(", ")
+	} else {
+		value.to_string()
+	}
+}
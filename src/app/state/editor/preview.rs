@@ -0,0 +1,446 @@
+use cosmic::{
+	Element,
+	iced::{
+		Color, Font, Length, Padding,
+		alignment::Horizontal,
+		border,
+		font::{Style, Weight},
+	},
+	iced_widget::{checkbox, column, horizontal_rule, rich_text, row, scrollable, text},
+	theme,
+	widget::{container, markdown::Url},
+};
+use std::path::PathBuf;
+
+use pulldown_cmark::{Alignment, BlockQuoteKind, HeadingLevel};
+
+use crate::{
+	app::message::Message,
+	trans,
+	utils::cfg::{flags::Flags, script::font},
+};
+
+pub mod diagram;
+pub mod image;
+pub mod parse;
+
+use diagram::Diagrams;
+use image::Images;
+
+/// A block of rendered Markdown
+#[derive(Debug, Clone)]
+pub enum Item {
+	Heading(HeadingLevel, Vec<Span>),
+	Paragraph(Vec<Span>),
+	CodeBlock(Vec<Span>),
+	List {
+		/// The first number of the list, if it is ordered
+		start: Option<u64>,
+		items: Vec<ListItem>,
+	},
+	Quote {
+		/// Set for GitHub alerts, which start with a marker such as `[!NOTE]`
+		alert: Option<BlockQuoteKind>,
+		items: Vec<Item>,
+	},
+	Table {
+		alignments: Vec<Alignment>,
+		/// The cells of every row, starting with the header
+		rows: Vec<Vec<Vec<Span>>>,
+	},
+	Rule,
+	/// A fenced code block in one of the languages supported by [diagram]
+	Diagram {
+		language: String,
+		source: String,
+	},
+	/// Display math, rendered as Unicode text
+	Math(String),
+	Image {
+		/// Only set for local images, resolved relative to the document
+		path: Option<PathBuf>,
+		alt: String,
+		width: Option<Width>,
+	},
+}
+
+/// An item of an [Item::List]
+#[derive(Debug, Clone, Default)]
+pub struct ListItem {
+	/// Only set for task list items
+	pub task: Option<Task>,
+	pub items: Vec<Item>,
+}
+
+/// The checkbox of a task list item
+#[derive(Debug, Clone, Copy)]
+pub struct Task {
+	pub checked: bool,
+	/// The byte offset of the checkbox within the document
+	pub offset: usize,
+}
+
+/// The width of an image, set by a `{width=...}` attribute after it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Width {
+	Pixels(f32),
+	/// Of the width of the preview, from 1 to 100
+	Percent(u16),
+}
+
+/// A piece of styled text within an [Item]
+#[derive(Debug, Clone, Default)]
+pub struct Span {
+	pub text: String,
+	pub strong: bool,
+	pub emphasis: bool,
+	pub strikethrough: bool,
+	pub code: bool,
+	pub link: Option<Link>,
+	/// The color provided by the syntax highlighter, if any
+	pub color: Option<Color>,
+}
+
+/// Where clicking on a [Span] leads
+#[derive(Debug, Clone)]
+pub enum Link {
+	Url(Url),
+	/// A line and byte column of the document, such as the definition of a footnote
+	Source(usize, usize),
+	/// A heading of the document
+	Anchor(String),
+	/// Another Markdown document, along with the anchor of one of its headings
+	File(PathBuf, Option<String>),
+	/// A note of the workspace by name, along with the anchor of one of its headings
+	Note(String, Option<String>),
+}
+
+/// The text size, fonts and styles used throughout the preview, resolved from the [Flags]
+#[derive(Clone, Copy)]
+struct Styles {
+	size: f32,
+	body: Font,
+	heading: Font,
+	code: Font,
+	/// Relative to the text size, from level 1 to 6
+	heading_sizes: [f32; 6],
+	heading_colors: [Option<Color>; 6],
+	/// The space between blocks
+	spacing: f32,
+	code_background: Option<Color>,
+	quote_border: Color,
+	quote_border_width: f32,
+	link: Color,
+	list_indent: f32,
+}
+
+impl Styles {
+	fn new(flags: &Flags, scale: f32) -> Self {
+		let size = flags.text_size * scale;
+		let style = &flags.preview_style;
+
+		Self {
+			size,
+			body: flags.preview_font.to_cosmic(font::SANS),
+			heading: flags.heading_font.to_cosmic(font::SANS),
+			code: flags.code_font.to_cosmic(font::MONO),
+			heading_sizes: style.heading_sizes(),
+			heading_colors: style.heading_colors(),
+			spacing: size * style.paragraph_spacing.max(0.),
+			code_background: style.code_background(),
+			quote_border: style
+				.quote_border()
+				.unwrap_or(flags.palette.text.scale_alpha(0.25)),
+			quote_border_width: style.quote_border_width.max(0.),
+			link: style.link_color().unwrap_or(flags.palette.primary),
+			list_indent: size * style.list_indent.max(0.),
+		}
+	}
+
+	fn heading_size(&self, level: HeadingLevel) -> f32 {
+		self.size * self.heading_sizes[level as usize - 1]
+	}
+}
+
+impl Item {
+	/// The lists of items nested within this one
+	pub fn children(&self) -> Vec<&[Item]> {
+		match self {
+			Self::List { items, .. } => items.iter().map(|item| item.items.as_slice()).collect(),
+			Self::Quote { items, .. } => vec![items],
+			_ => vec![],
+		}
+	}
+}
+
+impl Width {
+	fn length(self) -> Length {
+		match self {
+			Self::Pixels(pixels) => Length::Fixed(pixels),
+			Self::Percent(percent) => Length::FillPortion(percent),
+		}
+	}
+}
+
+/// Renders a list of [Item]s, with the text scaled by the provided amount
+pub fn view<'a>(
+	items: &'a [Item],
+	images: &'a Images,
+	diagrams: &'a Diagrams,
+	flags: &'a Flags,
+	scale: f32,
+) -> Element<'a, Message> {
+	let items = view_items(items, images, diagrams, flags, Styles::new(flags, scale));
+
+	match flags.preview_style.max_width() {
+		Some(width) => container(container(items).max_width(width))
+			.center_x(Length::Fill)
+			.into(),
+		None => items,
+	}
+}
+
+fn view_items<'a>(
+	items: &'a [Item],
+	images: &'a Images,
+	diagrams: &'a Diagrams,
+	flags: &'a Flags,
+	styles: Styles,
+) -> Element<'a, Message> {
+	column(
+		items
+			.iter()
+			.map(|item| item.view(images, diagrams, flags, styles)),
+	)
+	.width(Length::Fill)
+	.spacing(styles.spacing)
+	.into()
+}
+
+impl Item {
+	fn view<'a>(
+		&'a self,
+		images: &'a Images,
+		diagrams: &'a Diagrams,
+		flags: &'a Flags,
+		styles: Styles,
+	) -> Element<'a, Message> {
+		let size = styles.size;
+		let spacing = size * 0.625;
+
+		match self {
+			Self::Heading(level, spans) => rich_text(view_spans(spans, styles.heading, styles))
+				.size(styles.heading_size(*level))
+				.color_maybe(styles.heading_colors[*level as usize - 1])
+				.line_height(flags.heading_font.line_height())
+				.into(),
+
+			Self::Paragraph(spans) => rich_text(view_spans(spans, styles.body, styles))
+				.size(size)
+				.line_height(flags.preview_font.line_height())
+				.into(),
+
+			Self::CodeBlock(spans) => container(
+				scrollable(
+					container(
+						rich_text(view_spans(spans, styles.code, styles))
+							.size(size * 0.75)
+							.line_height(flags.code_font.line_height()),
+					)
+					.padding(spacing / 2.),
+				)
+				.direction(scrollable::Direction::Horizontal(
+					scrollable::Scrollbar::default()
+						.width(spacing / 2.)
+						.scroller_width(spacing / 2.),
+				)),
+			)
+			.width(Length::Fill)
+			.padding(spacing / 2.)
+			.class(match styles.code_background {
+				Some(color) => theme::Container::custom(move |_| container::Style {
+					background: Some(color.into()),
+					border: border::rounded(4),
+					..Default::default()
+				}),
+				None => theme::Container::Card,
+			})
+			.into(),
+
+			Self::List { start, items } => container(
+				column(items.iter().enumerate().map(|(i, item)| {
+					let marker: Element<'a, Message> = match (item.task, start) {
+						(Some(Task { checked, offset }), _) => checkbox("", checked)
+							.size(size)
+							.on_toggle(move |_| Message::ToggleTask(offset))
+							.into(),
+						(None, Some(start)) => text(format!("{}.", start + i as u64))
+							.size(size)
+							.font(styles.body)
+							.into(),
+						(None, None) => text("•").size(size).font(styles.body).into(),
+					};
+
+					row![
+						marker,
+						view_items(&item.items, images, diagrams, flags, styles)
+					]
+					.spacing(spacing)
+					.into()
+				}))
+				.spacing(spacing),
+			)
+			.padding(Padding::ZERO.left(styles.list_indent))
+			.into(),
+
+			Self::Quote { alert, items } => {
+				let color = match alert {
+					Some(BlockQuoteKind::Note | BlockQuoteKind::Important) => flags.palette.primary,
+					Some(BlockQuoteKind::Tip) => flags.palette.success,
+					Some(BlockQuoteKind::Warning | BlockQuoteKind::Caution) => flags.palette.danger,
+					None => styles.quote_border,
+				};
+
+				let items = view_items(items, images, diagrams, flags, styles);
+				let content: Element<'a, Message> = match alert {
+					Some(alert) => column![
+						text(alert_title(*alert))
+							.size(size)
+							.font(Font {
+								weight: Weight::Bold,
+								..styles.body
+							})
+							.color(color),
+						items
+					]
+					.spacing(spacing)
+					.into(),
+					None => items,
+				};
+
+				container(content)
+					.width(Length::Fill)
+					.padding(spacing)
+					.class(outline(color, styles.quote_border_width))
+					.into()
+			}
+
+			Self::Table { alignments, rows } => {
+				container(
+					column(rows.iter().enumerate().map(|(i, cells)| {
+						// The header is bold
+						let font = match i {
+							0 => Font {
+								weight: Weight::Bold,
+								..styles.body
+							},
+							_ => styles.body,
+						};
+
+						row(cells.iter().enumerate().map(|(j, spans)| {
+							let align = match alignments.get(j) {
+								Some(Alignment::Center) => Horizontal::Center,
+								Some(Alignment::Right) => Horizontal::Right,
+								_ => Horizontal::Left,
+							};
+
+							container(
+								rich_text(view_spans(spans, font, styles))
+									.size(size)
+									.line_height(flags.preview_font.line_height()),
+							)
+							.width(Length::Fill)
+							.align_x(align)
+							.into()
+						}))
+						.spacing(spacing)
+						.into()
+					}))
+					.spacing(spacing / 2.),
+				)
+				.width(Length::Fill)
+				.padding(spacing)
+				.class(outline(flags.palette.text.scale_alpha(0.25), 1.))
+				.into()
+			}
+
+			Self::Rule => horizontal_rule(1).into(),
+
+			Self::Math(math) => text(math)
+				.size(size * 1.25)
+				.font(Font {
+					style: Style::Italic,
+					..styles.body
+				})
+				.width(Length::Fill)
+				.align_x(Horizontal::Center)
+				.into(),
+
+			Self::Image { path, alt, width } => images.view(path.as_deref(), alt, *width, size),
+
+			Self::Diagram { language, source } => diagrams.view(language, source, flags, size),
+		}
+	}
+}
+
+fn alert_title(alert: BlockQuoteKind) -> String {
+	match alert {
+		BlockQuoteKind::Note => trans!("alert_note"),
+		BlockQuoteKind::Tip => trans!("alert_tip"),
+		BlockQuoteKind::Important => trans!("alert_important"),
+		BlockQuoteKind::Warning => trans!("alert_warning"),
+		BlockQuoteKind::Caution => trans!("alert_caution"),
+	}
+}
+
+fn outline<'a>(color: Color, width: f32) -> theme::Container<'a> {
+	theme::Container::custom(move |_| container::Style {
+		border: border::rounded(4).color(color).width(width),
+		..Default::default()
+	})
+}
+
+fn view_spans(spans: &[Span], base: Font, styles: Styles) -> Vec<text::Span<'static, Message>> {
+	spans.iter().map(|span| span.view(base, styles)).collect()
+}
+
+impl Span {
+	fn view(&self, base: Font, styles: Styles) -> text::Span<'static, Message> {
+		let span = text::Span::new(self.text.clone()).strikethrough(self.strikethrough);
+
+		let span = if self.code {
+			span.font(styles.code)
+				.color(Color::WHITE)
+				.background(Color::from_rgb8(0x11, 0x11, 0x11))
+				.border(border::rounded(2))
+				.padding(Padding::from([0, 1]))
+		} else {
+			span.font(Font {
+				weight: if self.strong {
+					Weight::Bold
+				} else {
+					base.weight
+				},
+				style: if self.emphasis {
+					Style::Italic
+				} else {
+					base.style
+				},
+				..base
+			})
+		};
+
+		let span = span.color_maybe(self.color);
+
+		match &self.link {
+			Some(link) => span.color(styles.link).link(match link {
+				Link::Url(url) => Message::Url(url.clone()),
+				Link::Source(line, column) => Message::GoTo(*line, *column),
+				Link::Anchor(anchor) => Message::ScrollToAnchor(anchor.clone()),
+				Link::File(path, anchor) => Message::OpenLink(path.clone(), anchor.clone()),
+				Link::Note(note, anchor) => Message::OpenNote(note.clone(), anchor.clone()),
+			}),
+			None => span,
+		}
+	}
+}
@@ -0,0 +1,259 @@
+//! Renders diagram code blocks, such as Graphviz and Mermaid, to SVG
+
+use std::{
+	collections::{HashMap, HashSet},
+	hash::{DefaultHasher, Hash, Hasher},
+	io::{Read, Write},
+	panic::{self, AssertUnwindSafe},
+	process::{Child, Command, ExitStatus, Stdio},
+	thread,
+	time::{Duration, Instant},
+};
+
+use cosmic::{
+	Element,
+	app::Task,
+	iced::Length,
+	iced_widget::{column, text},
+	theme,
+	widget::{container, svg},
+};
+use layout::{
+	backends::svg::SVGWriter,
+	gv::{DotParser, GraphBuilder},
+};
+
+use crate::{app::message::Message, trans, utils::cfg::flags::Flags};
+
+use super::Item;
+
+/// The languages of code blocks that are rendered as diagrams
+const LANGUAGES: [&str; 5] = ["dot", "graphviz", "mermaid", "plantuml", "d2"];
+
+/// How long a diagram may take to render before it fails and its command is killed
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The diagram language of a fenced code block, if it is one, from the first word of its info
+/// string
+pub fn language(info: &str) -> Option<String> {
+	let language = info.split_whitespace().next()?.to_lowercase();
+	LANGUAGES.contains(&language.as_str()).then_some(language)
+}
+
+/// The state of a diagram in the cache
+#[derive(Debug, Clone)]
+pub enum Diagram {
+	Rendering,
+	Rendered(svg::Handle),
+	Failed(String),
+}
+
+/// Rendered diagrams, keyed by a hash of their language and source
+#[derive(Default)]
+pub struct Diagrams {
+	cache: HashMap<u64, Diagram>,
+}
+
+impl Diagrams {
+	/// Starts rendering every diagram of the items that is not cached, forgetting the diagrams
+	/// that are no longer there.
+	///
+	/// The preview is only parsed once the text stops changing, so diagrams are not rendered
+	/// while their source is being typed.
+	pub fn render(&mut self, items: &[Item], flags: &Flags) -> Task<Message> {
+		let mut diagrams = vec![];
+		collect(items, &mut diagrams);
+
+		let hashes = diagrams
+			.iter()
+			.map(|(language, source)| hash(language, source))
+			.collect::<HashSet<u64>>();
+		self.cache.retain(|hash, _| hashes.contains(hash));
+
+		let tasks = diagrams
+			.into_iter()
+			.filter(|(language, source)| !self.cache.contains_key(&hash(language, source)))
+			.map(|(language, source)| {
+				let hash = hash(&language, &source);
+				let command = flags.diagram_command(&language);
+				self.cache.insert(hash, Diagram::Rendering);
+
+				Task::future(async move {
+					let render = tokio::task::spawn_blocking(move || {
+						render(&language, &source, command.as_deref())
+					});
+
+					// Commands are killed once they time out, but the built in renderer cannot be
+					// stopped, so it is only no longer waited for
+					let diagram = match tokio::time::timeout(TIMEOUT, render).await {
+						Ok(Ok(Ok(svg))) => {
+							Diagram::Rendered(svg::Handle::from_memory(svg.into_bytes()))
+						}
+						Ok(Ok(Err(e))) => Diagram::Failed(e),
+						Ok(Err(e)) => Diagram::Failed(e.to_string()),
+						Err(_) => Diagram::Failed(timed_out()),
+					};
+					cosmic::Action::App(Message::DiagramRendered(hash, diagram))
+				})
+			})
+			.collect::<Vec<Task<Message>>>();
+
+		Task::batch(tasks)
+	}
+
+	/// Caches a rendered diagram, unless it was removed from the document while rendering
+	pub fn rendered(&mut self, hash: u64, diagram: Diagram) {
+		if let Some(cached) = self.cache.get_mut(&hash) {
+			*cached = diagram;
+		}
+	}
+
+	/// Renders a diagram, or the reason it could not be rendered
+	pub fn view<'a>(
+		&self,
+		language: &str,
+		source: &str,
+		flags: &Flags,
+		size: f32,
+	) -> Element<'a, Message> {
+		match self.cache.get(&hash(language, source)) {
+			Some(Diagram::Rendered(handle)) => {
+				svg::Svg::new(handle.clone()).width(Length::Shrink).into()
+			}
+
+			Some(Diagram::Failed(error)) => container(column![
+				text(trans!("diagram_failed", language = language)).size(size),
+				text(error.clone())
+					.size(size * 0.75)
+					.color(flags.palette.danger),
+			])
+			.width(Length::Fill)
+			.padding(size / 2.)
+			.class(theme::Container::Card)
+			.into(),
+
+			_ => container(text(trans!("diagram_rendering", language = language)).size(size))
+				.width(Length::Fill)
+				.padding(size / 2.)
+				.class(theme::Container::Card)
+				.into(),
+		}
+	}
+}
+
+/// Every diagram within the items, along with its language
+fn collect(items: &[Item], diagrams: &mut Vec<(String, String)>) {
+	for item in items {
+		match item {
+			Item::Diagram { language, source } => diagrams.push((language.clone(), source.clone())),
+			item => {
+				for items in item.children() {
+					collect(items, diagrams);
+				}
+			}
+		}
+	}
+}
+
+fn hash(language: &str, source: &str) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	(language, source).hash(&mut hasher);
+	hasher.finish()
+}
+
+/// Renders a diagram to SVG, preferring the built in renderer and falling back to the command
+fn render(language: &str, source: &str, command: Option<&str>) -> Result<String, String> {
+	let builtin = match language {
+		"dot" | "graphviz" => Some(render_dot(source)),
+		_ => None,
+	};
+
+	match (builtin, command) {
+		(Some(Ok(svg)), _) => Ok(svg),
+		(_, Some(command)) => run(command, source),
+		(Some(Err(e)), None) => Err(e),
+		(None, None) => Err(trans!("diagram_no_renderer", language = language)),
+	}
+}
+
+fn render_dot(source: &str) -> Result<String, String> {
+	let graph = DotParser::new(source).process()?;
+
+	// The layout engine panics on some graphs it does not support
+	panic::catch_unwind(AssertUnwindSafe(|| {
+		let mut builder = GraphBuilder::new();
+		builder.visit_graph(&graph);
+
+		let mut svg = SVGWriter::new();
+		builder.get().do_it(false, false, false, &mut svg);
+		svg.finalize()
+	}))
+	.map_err(|_| trans!("diagram_unsupported"))
+}
+
+/// Runs the command, passing the source through stdin and reading SVG from stdout
+fn run(command: &str, source: &str) -> Result<String, String> {
+	let mut parts = command.split_whitespace();
+	let program = parts.next().ok_or_else(|| trans!("diagram_no_command"))?;
+
+	let mut child = Command::new(program)
+		.args(parts)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.map_err(|e| format!("{program}: {e}"))?;
+
+	// The source is written while the output is read, since the command may fill the pipe of its
+	// output before it has read all of the source
+	let (status, stdout, stderr) = thread::scope(|scope| {
+		if let Some(mut stdin) = child.stdin.take() {
+			// A command that fails may stop reading early, which its error explains better
+			scope.spawn(move || stdin.write_all(source.as_bytes()));
+		}
+		let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+		let stdout = scope.spawn(move || read(stdout));
+		let stderr = scope.spawn(move || read(stderr));
+
+		// Killing the command closes its output, so it can still be read afterwards
+		let status = wait(&mut child);
+		if status.is_err() {
+			let _ = child.kill();
+			let _ = child.wait();
+		}
+		let output = |reader: thread::ScopedJoinHandle<_>| reader.join().unwrap_or_default();
+		(status, output(stdout), output(stderr))
+	});
+
+	match status {
+		Ok(status) if status.success() => Ok(String::from_utf8_lossy(&stdout).into()),
+		Ok(_) => Err(String::from_utf8_lossy(&stderr).trim().into()),
+		Err(e) => Err(e),
+	}
+}
+
+/// Waits for the command to exit, failing if it takes longer than the timeout
+fn wait(child: &mut Child) -> Result<ExitStatus, String> {
+	let deadline = Instant::now() + TIMEOUT;
+	loop {
+		match child.try_wait() {
+			Ok(Some(status)) => return Ok(status),
+			Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(20)),
+			Ok(None) => return Err(timed_out()),
+			Err(e) => return Err(e.to_string()),
+		}
+	}
+}
+
+/// Reads all of the output of a command
+fn read(output: Option<impl Read>) -> Vec<u8> {
+	let mut bytes = vec![];
+	if let Some(mut output) = output {
+		let _ = output.read_to_end(&mut bytes);
+	}
+	bytes
+}
+
+fn timed_out() -> String {
+	trans!("diagram_timed_out", seconds = TIMEOUT.as_secs())
+}
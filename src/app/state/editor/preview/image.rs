@@ -0,0 +1,199 @@
+//! Loads the local images shown in the preview
+
+use std::{
+	collections::{HashMap, HashSet},
+	fs,
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
+
+use cosmic::{
+	Element,
+	app::Task,
+	iced::Length,
+	iced_widget::{row, text},
+	theme,
+	widget::{container, horizontal_space, image, svg},
+};
+use tracing::warn;
+
+use crate::{app::message::Message, trans};
+
+use super::{Item, Width};
+
+/// The state of an image in the cache
+#[derive(Debug, Clone)]
+pub enum Image {
+	Loading,
+	Raster(image::Handle),
+	Svg(svg::Handle),
+	Missing,
+	/// Not in a format that can be decoded
+	Invalid,
+	/// Larger than the "max_image_size" flag allows
+	TooLarge,
+}
+
+/// The images of a document, keyed by their path and reloaded when the file is modified
+#[derive(Default)]
+pub struct Images {
+	cache: HashMap<PathBuf, (Option<SystemTime>, Image)>,
+}
+
+impl Images {
+	/// Starts loading every image of the items that is not cached, or has changed since it was,
+	/// forgetting the images that are no longer there
+	pub fn load(&mut self, items: &[Item], max_size: u64) -> Task<Message> {
+		let mut paths = vec![];
+		collect(items, &mut paths);
+
+		let current = paths.iter().collect::<HashSet<_>>();
+		self.cache.retain(|path, _| current.contains(path));
+
+		let tasks = paths
+			.into_iter()
+			.filter_map(|path| {
+				let modified = modified(&path);
+				if self
+					.cache
+					.get(&path)
+					.is_some_and(|(cached, _)| *cached == modified)
+				{
+					return None;
+				}
+
+				self.cache.insert(path.clone(), (modified, Image::Loading));
+				Some(Task::future(load(path, modified, max_size)))
+			})
+			.collect::<Vec<Task<Message>>>();
+
+		Task::batch(tasks)
+	}
+
+	/// Caches a loaded image, unless it was removed from the document while loading
+	pub fn loaded(&mut self, path: PathBuf, modified: Option<SystemTime>, image: Image) {
+		if let Some(cached) = self.cache.get_mut(&path) {
+			*cached = (modified, image);
+		}
+	}
+
+	/// Renders an image, or a placeholder with its alt text if it cannot be shown
+	pub fn view<'a>(
+		&self,
+		path: Option<&Path>,
+		alt: &str,
+		width: Option<Width>,
+		size: f32,
+	) -> Element<'a, Message> {
+		let image = path.and_then(|path| self.cache.get(path).map(|(_, image)| image));
+
+		let image: Element<'a, Message> = match image {
+			Some(Image::Raster(handle)) => image::Image::new(handle.clone())
+				.width(width.map_or(Length::Shrink, Width::length))
+				.into(),
+			Some(Image::Svg(handle)) => svg::Svg::new(handle.clone())
+				.width(width.map_or(Length::Shrink, Width::length))
+				.into(),
+
+			image => {
+				let message = match (image, path) {
+					(Some(Image::Loading), _) => trans!("image_loading", alt = alt),
+					(Some(Image::TooLarge), _) => trans!("image_too_large", alt = alt),
+					(Some(Image::Invalid), _) => trans!("image_invalid", alt = alt),
+					(_, Some(path)) => trans!(
+						"image_missing",
+						alt = alt,
+						path = path.to_string_lossy().to_string()
+					),
+					(_, None) => trans!("image_unsupported", alt = alt),
+				};
+
+				return container(text(message).size(size))
+					.padding(size / 2.)
+					.class(theme::Container::Card)
+					.into();
+			}
+		};
+
+		// Percentages are the portion of the preview taken up by the image
+		match width {
+			Some(Width::Percent(percent)) if percent < 100 => row![
+				image,
+				horizontal_space().width(Length::FillPortion(100 - percent))
+			]
+			.into(),
+			_ => image,
+		}
+	}
+}
+
+/// Every local image within the items
+fn collect(items: &[Item], paths: &mut Vec<PathBuf>) {
+	for item in items {
+		match item {
+			Item::Image {
+				path: Some(path), ..
+			} => paths.push(path.clone()),
+			item => {
+				for items in item.children() {
+					collect(items, paths);
+				}
+			}
+		}
+	}
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+	fs::metadata(path)
+		.and_then(|metadata| metadata.modified())
+		.ok()
+}
+
+async fn load(
+	path: PathBuf,
+	modified: Option<SystemTime>,
+	max_size: u64,
+) -> cosmic::Action<Message> {
+	let read = {
+		let path = path.clone();
+		move || read(&path, max_size)
+	};
+
+	let image = tokio::task::spawn_blocking(read).await.unwrap_or_else(|e| {
+		warn!("Image {path:?} could not be loaded: {e}");
+		Image::Missing
+	});
+	cosmic::Action::App(Message::ImageLoaded(path, modified, image))
+}
+
+/// Reads the image, decoding it unless it is an SVG, so that it is not decoded while drawing
+fn read(path: &Path, max_size: u64) -> Image {
+	let bytes = match fs::metadata(path) {
+		Ok(metadata) if metadata.len() > max_size => return Image::TooLarge,
+		Ok(_) => fs::read(path),
+		Err(_) => return Image::Missing,
+	};
+	let bytes = match bytes {
+		Ok(bytes) => bytes,
+		Err(e) => {
+			warn!("Image {path:?} could not be read: {e}");
+			return Image::Missing;
+		}
+	};
+
+	if path.extension().is_some_and(|ext| ext == "svg") {
+		return Image::Svg(svg::Handle::from_memory(bytes));
+	}
+
+	match ::image::load_from_memory(&bytes) {
+		Ok(decoded) => {
+			let decoded = decoded.into_rgba8();
+			let (width, height) = decoded.dimensions();
+			Image::Raster(image::Handle::from_rgba(width, height, decoded.into_raw()))
+		}
+		Err(e) => {
+			warn!("Image {path:?} could not be decoded: {e}");
+			Image::Invalid
+		}
+	}
+}
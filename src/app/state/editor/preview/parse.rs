@@ -0,0 +1,668 @@
+use std::{
+	collections::HashMap,
+	ops::Range,
+	path::{Path, PathBuf},
+};
+
+use cosmic::{
+	iced::highlighter::{self, Highlighter},
+	iced_core::text::Highlighter as _,
+	widget::markdown::Url,
+};
+use percent_encoding::percent_decode_str;
+use pulldown_cmark::{
+	Alignment, BlockQuoteKind, CodeBlockKind, Event, Parser, Tag, TagEnd, TextMergeWithOffset,
+};
+
+use crate::{app::state::editor::buffer, md, trans};
+
+use super::{Item, Link, ListItem, Span, Task, Width, diagram};
+
+/// A block containing other items that is still being parsed
+enum Block {
+	List {
+		start: Option<u64>,
+		items: Vec<ListItem>,
+	},
+	Quote {
+		alert: Option<BlockQuoteKind>,
+		items: Vec<Item>,
+	},
+	Footnote {
+		label: String,
+		items: Vec<Item>,
+	},
+}
+
+/// A table that is still being parsed
+struct Table {
+	alignments: Vec<Alignment>,
+	rows: Vec<Vec<Vec<Span>>>,
+}
+
+/// A footnote that is referenced, numbered by the order it is first referenced in
+struct Footnote {
+	label: String,
+	/// The line and byte column of every reference, which the footnote links back to
+	references: Vec<(usize, usize)>,
+}
+
+/// An image that is still being parsed
+struct Image {
+	path: Option<PathBuf>,
+	/// Where the image leads when it is shown as a link to it
+	link: Option<Link>,
+	alt: String,
+}
+
+/// Converts the events produced by [Parser] into [Item]s
+struct Builder<'a> {
+	markdown: &'a str,
+	theme: highlighter::Theme,
+	/// The path of the document, which images and links are resolved relative to
+	document: Option<PathBuf>,
+	items: Vec<Item>,
+	blocks: Vec<Block>,
+	spans: Vec<Span>,
+	/// The style applied to new [Span]s
+	style: Span,
+	highlighter: Option<Highlighter>,
+	/// The language of the diagram being parsed, whose source is collected as plain spans
+	diagram: Option<String>,
+	image: Option<Image>,
+	table: Option<Table>,
+	/// Whether the parser is inside of a heading
+	heading: bool,
+	/// The line and byte column of every footnote definition, by label
+	definitions: HashMap<String, (usize, usize)>,
+	footnotes: Vec<Footnote>,
+	/// The content of every footnote definition, by label
+	notes: HashMap<String, Vec<Item>>,
+	/// Whether the last event ended an image, so that text may set its width
+	after_image: bool,
+	/// How many blocks that cannot be rendered the parser is inside of
+	skip: usize,
+}
+
+/// Parses Markdown into [Item]s, highlighting code blocks with the provided theme.
+///
+/// Local images and links are resolved relative to the directory of the document, if it has one.
+pub fn parse(markdown: &str, theme: highlighter::Theme, document: Option<&Path>) -> Vec<Item> {
+	// Wiki links are split into several text events, since their brackets could start links
+	let events =
+		TextMergeWithOffset::new(Parser::new_ext(markdown, md::EXTENSIONS).into_offset_iter())
+			.collect::<Vec<_>>();
+
+	// Footnotes can be referenced before they are defined
+	let definitions = events
+		.iter()
+		.filter_map(|(event, range)| match event {
+			Event::Start(Tag::FootnoteDefinition(label)) => {
+				Some((label.to_string(), buffer::position(markdown, range.start)))
+			}
+			_ => None,
+		})
+		.collect();
+
+	let mut builder = Builder {
+		markdown,
+		theme,
+		document: document.map(Path::to_path_buf),
+		items: vec![],
+		blocks: vec![],
+		spans: vec![],
+		style: Span::default(),
+		highlighter: None,
+		diagram: None,
+		image: None,
+		table: None,
+		heading: false,
+		definitions,
+		footnotes: vec![],
+		notes: HashMap::new(),
+		after_image: false,
+		skip: 0,
+	};
+
+	for (event, range) in events {
+		builder.event(event, range);
+	}
+
+	builder.finish()
+}
+
+impl Builder<'_> {
+	fn event(&mut self, event: Event, range: Range<usize>) {
+		let after_image = std::mem::take(&mut self.after_image);
+
+		match event {
+			Event::Start(Tag::MetadataBlock(_)) => self.skip += 1,
+			Event::End(TagEnd::MetadataBlock(_)) => self.skip -= 1,
+			_ if self.skip > 0 => (),
+
+			Event::Start(tag) => self.start(tag),
+			Event::End(tag) => self.end(tag),
+
+			Event::Text(text) if after_image => {
+				let rest = self.width(&text);
+				if !rest.is_empty() {
+					self.text(rest);
+				}
+			}
+			Event::Text(text) => self.text(&text),
+			Event::InlineMath(latex) => self.span(Span {
+				text: md::math::to_unicode(&latex),
+				emphasis: true,
+				..self.style.clone()
+			}),
+			// Display math is placed between the text before and after it
+			Event::DisplayMath(latex) => {
+				if !self.spans.is_empty() {
+					let spans = self.take_spans();
+					self.produce(Item::Paragraph(spans));
+				}
+				self.produce(Item::Math(md::math::to_unicode(&latex)));
+			}
+			Event::Code(code) => self.span(Span {
+				text: code.into_string(),
+				code: true,
+				..self.style.clone()
+			}),
+			Event::SoftBreak => self.span(Span {
+				text: " ".into(),
+				..self.style.clone()
+			}),
+			Event::HardBreak => self.span(Span {
+				text: "\n".into(),
+				..self.style.clone()
+			}),
+			Event::Rule => self.produce(Item::Rule),
+
+			Event::TaskListMarker(checked) => {
+				if let Some(Block::List { items, .. }) = self.blocks.last_mut() {
+					if let Some(item) = items.last_mut() {
+						item.task = Some(Task {
+							checked,
+							offset: range.start,
+						});
+					}
+				}
+			}
+			Event::FootnoteReference(label) => self.reference(&label, range.start),
+
+			_ => (),
+		}
+	}
+
+	fn start(&mut self, tag: Tag) {
+		match tag {
+			Tag::Strong => self.style.strong = true,
+			Tag::Emphasis => self.style.emphasis = true,
+			Tag::Strikethrough => self.style.strikethrough = true,
+			Tag::Link { dest_url, .. } => {
+				self.style.link = link(&dest_url, self.document.as_deref());
+			}
+
+			Tag::Image { dest_url, .. } => {
+				self.image = Some(Image {
+					path: resolve(&dest_url, self.dir()),
+					link: link(&dest_url, self.document.as_deref()),
+					alt: String::new(),
+				});
+			}
+			Tag::Heading { .. } => self.heading = true,
+
+			Tag::List(start) => self.blocks.push(Block::List {
+				start,
+				items: vec![],
+			}),
+			Tag::Item => {
+				if let Some(Block::List { items, .. }) = self.blocks.last_mut() {
+					items.push(ListItem::default());
+				}
+			}
+			Tag::BlockQuote(alert) => self.blocks.push(Block::Quote {
+				alert,
+				items: vec![],
+			}),
+			Tag::FootnoteDefinition(label) => self.blocks.push(Block::Footnote {
+				label: label.to_string(),
+				items: vec![],
+			}),
+
+			Tag::Table(alignments) => {
+				self.table = Some(Table {
+					alignments,
+					rows: vec![],
+				});
+			}
+			Tag::TableHead | Tag::TableRow => {
+				if let Some(table) = &mut self.table {
+					table.rows.push(vec![]);
+				}
+			}
+
+			Tag::CodeBlock(CodeBlockKind::Fenced(info)) if diagram::language(&info).is_some() => {
+				self.diagram = diagram::language(&info);
+			}
+			Tag::CodeBlock(CodeBlockKind::Fenced(language)) => {
+				self.highlighter = Some(Highlighter::new(&highlighter::Settings {
+					theme: self.theme,
+					token: language.to_string(),
+				}));
+			}
+
+			_ => (),
+		}
+	}
+
+	fn end(&mut self, tag: TagEnd) {
+		match tag {
+			TagEnd::Strong => self.style.strong = false,
+			TagEnd::Emphasis => self.style.emphasis = false,
+			TagEnd::Strikethrough => self.style.strikethrough = false,
+			TagEnd::Link => self.style.link = None,
+
+			// Images are placed between the text before and after them, except in table cells and
+			// headings, which only contain text and so link to them instead
+			TagEnd::Image => {
+				let Some(Image { path, link, alt }) = self.image.take() else {
+					return;
+				};
+
+				if self.inline() {
+					let text = if alt.is_empty() { trans!("image") } else { alt };
+					self.span(Span {
+						text,
+						emphasis: true,
+						link: self.style.link.clone().or(link),
+						..self.style.clone()
+					});
+					self.after_image = true;
+					return;
+				}
+
+				if !self.spans.is_empty() {
+					let spans = self.take_spans();
+					self.produce(Item::Paragraph(spans));
+				}
+				self.produce(Item::Image {
+					path,
+					alt,
+					width: None,
+				});
+				self.after_image = true;
+			}
+
+			TagEnd::Heading(level) => {
+				self.heading = false;
+				let spans = self.take_spans();
+				self.produce(Item::Heading(level, spans));
+			}
+			TagEnd::Paragraph if !self.spans.is_empty() => {
+				let spans = self.take_spans();
+				self.produce(Item::Paragraph(spans));
+			}
+			TagEnd::Item if !self.spans.is_empty() => {
+				let spans = self.take_spans();
+				self.produce(Item::Paragraph(spans));
+			}
+			TagEnd::List(_) => {
+				if let Some(Block::List { start, items }) = self.blocks.pop() {
+					self.produce(Item::List { start, items });
+				}
+			}
+			TagEnd::BlockQuote(_) => {
+				if let Some(Block::Quote { alert, items }) = self.blocks.pop() {
+					self.produce(Item::Quote { alert, items });
+				}
+			}
+			TagEnd::FootnoteDefinition => {
+				if let Some(Block::Footnote { label, items }) = self.blocks.pop() {
+					self.notes.insert(label, items);
+				}
+			}
+
+			TagEnd::TableCell => {
+				let spans = self.take_spans();
+				if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+					row.push(spans);
+				}
+			}
+			TagEnd::Table => {
+				if let Some(Table { alignments, rows }) = self.table.take() {
+					self.produce(Item::Table { alignments, rows });
+				}
+			}
+			TagEnd::CodeBlock if self.diagram.is_some() => {
+				let language = self.diagram.take().unwrap_or_default();
+				let source = self
+					.take_spans()
+					.into_iter()
+					.map(|span| span.text)
+					.collect();
+				self.produce(Item::Diagram { language, source });
+			}
+			TagEnd::CodeBlock => {
+				self.highlighter = None;
+
+				let spans = self.take_spans();
+				self.produce(Item::CodeBlock(spans));
+			}
+
+			_ => (),
+		}
+	}
+
+	fn text(&mut self, text: &str) {
+		if let Some(image) = &mut self.image {
+			image.alt.push_str(text);
+			return;
+		}
+
+		if let Some(highlighter) = &mut self.highlighter {
+			// The text of a code block is merged, but highlighted line by line
+			for line in text.split_inclusive('\n') {
+				let highlighted = highlighter
+					.highlight_line(line)
+					.map(|(range, highlight)| Span {
+						text: line[range].into(),
+						color: highlight.color(),
+						..Span::default()
+					})
+					.collect::<Vec<_>>();
+
+				self.spans.extend(highlighted);
+			}
+			return;
+		}
+
+		let mut links = vec![];
+
+		// Bare URLs are turned into links like on GitHub, and wiki links into links to notes,
+		// except in diagrams and existing links
+		if self.diagram.is_none() && self.style.link.is_none() {
+			let wiki = md::wiki::find(text);
+
+			links.extend(
+				autolinks(text)
+					.into_iter()
+					.filter(|(range, _)| {
+						!wiki.iter().any(|link| {
+							link.range.start < range.end && range.start < link.range.end
+						})
+					})
+					.map(|(range, url)| (range.clone(), text[range].to_string(), Link::Url(url))),
+			);
+			links.extend(wiki.into_iter().map(|link| {
+				let label = link.label();
+				let anchor = link.anchor.as_deref().map(md::anchor::slug);
+				let target = if link.note.is_empty() {
+					Link::Anchor(anchor.unwrap_or_default())
+				} else {
+					Link::Note(link.note, anchor)
+				};
+				(link.range, label, target)
+			}));
+			links.sort_by_key(|(range, ..)| range.start);
+		}
+
+		let mut start = 0;
+		for (range, label, link) in links {
+			if start < range.start {
+				self.span(Span {
+					text: text[start..range.start].into(),
+					..self.style.clone()
+				});
+			}
+			self.span(Span {
+				text: label,
+				link: Some(link),
+				..self.style.clone()
+			});
+			start = range.end;
+		}
+
+		if start < text.len() {
+			self.span(Span {
+				text: text[start..].into(),
+				..self.style.clone()
+			});
+		}
+	}
+
+	/// Links to the definition of a footnote, numbering it if it has not been referenced before
+	fn reference(&mut self, label: &str, offset: usize) {
+		let position = buffer::position(self.markdown, offset);
+
+		let number = match self
+			.footnotes
+			.iter()
+			.position(|footnote| footnote.label == label)
+		{
+			Some(i) => {
+				self.footnotes[i].references.push(position);
+				i + 1
+			}
+			None => {
+				self.footnotes.push(Footnote {
+					label: label.into(),
+					references: vec![position],
+				});
+				self.footnotes.len()
+			}
+		};
+
+		let (line, column) = self.definitions.get(label).copied().unwrap_or(position);
+		self.span(Span {
+			text: format!("[{number}]"),
+			link: Some(Link::Source(line, column)),
+			..self.style.clone()
+		});
+	}
+
+	/// Sets the width of the last image from a `{width=...}` attribute at the start of the text,
+	/// returning the rest of the text
+	fn width<'a>(&mut self, text: &'a str) -> &'a str {
+		let Some((width, rest)) = width(text) else {
+			return text;
+		};
+
+		if !self.inline() {
+			if let Some(Item::Image { width: w, .. }) = self.target().last_mut() {
+				*w = Some(width);
+			}
+		}
+
+		rest
+	}
+
+	/// Whether images are shown as links, since they are within text that cannot contain them
+	fn inline(&self) -> bool {
+		self.table.is_some() || self.heading
+	}
+
+	fn dir(&self) -> Option<&Path> {
+		self.document.as_deref().and_then(Path::parent)
+	}
+
+	fn span(&mut self, span: Span) {
+		self.spans.push(span);
+	}
+
+	fn take_spans(&mut self) -> Vec<Span> {
+		std::mem::take(&mut self.spans)
+	}
+
+	/// Adds an [Item] to the innermost block, or to the document if not in a block
+	fn produce(&mut self, item: Item) {
+		self.target().push(item);
+	}
+
+	/// The items of the innermost block, or of the document if not in a block
+	fn target(&mut self) -> &mut Vec<Item> {
+		let items = match self.blocks.last_mut() {
+			Some(Block::List { items, .. }) => items.last_mut().map(|item| &mut item.items),
+			Some(Block::Quote { items, .. } | Block::Footnote { items, .. }) => Some(items),
+			None => None,
+		};
+
+		items.unwrap_or(&mut self.items)
+	}
+
+	/// Places the footnotes that are referenced at the end of the document, each linking back to
+	/// its references
+	fn finish(mut self) -> Vec<Item> {
+		if self.footnotes.is_empty() {
+			return self.items;
+		}
+
+		let footnotes = self
+			.footnotes
+			.into_iter()
+			.map(|Footnote { label, references }| {
+				let mut items = self.notes.remove(&label).unwrap_or_default();
+				let back = references.into_iter().map(|(line, column)| Span {
+					text: " ↩".into(),
+					link: Some(Link::Source(line, column)),
+					..Span::default()
+				});
+
+				match items.last_mut() {
+					Some(Item::Paragraph(spans)) => spans.extend(back),
+					_ => items.push(Item::Paragraph(back.collect())),
+				}
+
+				ListItem { task: None, items }
+			})
+			.collect();
+
+		self.items.push(Item::Rule);
+		self.items.push(Item::List {
+			start: Some(1),
+			items: footnotes,
+		});
+		self.items
+	}
+}
+
+/// Where a link leads, or None if it cannot be followed
+fn link(url: &str, document: Option<&Path>) -> Option<Link> {
+	if let Some(anchor) = url.strip_prefix('#') {
+		return Some(Link::Anchor(anchor.into()));
+	}
+
+	if let Ok(parsed) = Url::parse(url) {
+		// Other schemes could run programs the reader does not expect, so they are not followed
+		match parsed.scheme() {
+			"http" | "https" | "mailto" => return Some(Link::Url(parsed)),
+			"file" => (),
+			_ => return None,
+		}
+	}
+
+	let path = resolve(url, document.and_then(Path::parent))?;
+	let anchor = url
+		.split_once('#')
+		.map(|(_, anchor)| anchor.to_string())
+		.filter(|anchor| !anchor.is_empty());
+
+	if Some(path.as_path()) == document {
+		anchor.map(Link::Anchor)
+	} else if md::is_markdown(&path) {
+		Some(Link::File(path, anchor))
+	} else {
+		// Other files are opened by the program the system uses for them
+		Url::from_file_path(&path).ok().map(Link::Url)
+	}
+}
+
+/// The path of a local file, or None if it is remote or relative to a document that is not saved
+pub fn resolve(url: &str, dir: Option<&Path>) -> Option<PathBuf> {
+	let url = url.split(['?', '#']).next().unwrap_or_default();
+	let url = url.strip_prefix("file://").unwrap_or(url);
+	let url = percent_decode_str(url).decode_utf8_lossy();
+
+	if url.contains("://") || url.is_empty() {
+		return None;
+	}
+
+	let path = match url.strip_prefix("~/") {
+		Some(url) => dirs::home_dir()?.join(url),
+		None => PathBuf::from(url.as_ref()),
+	};
+
+	if path.is_absolute() {
+		Some(path)
+	} else {
+		dir.map(|dir| dir.join(path))
+	}
+}
+
+/// The bare URLs within the text, starting with "http://", "https://" or "www."
+fn autolinks(text: &str) -> Vec<(Range<usize>, Url)> {
+	let mut links = vec![];
+	let mut offset = 0;
+
+	for word in text.split_inclusive(char::is_whitespace) {
+		let start = offset;
+		offset += word.len();
+
+		let word = word.trim_end();
+		let lead = word.len() - word.trim_start_matches(['(', '*', '_', '~']).len();
+		let link = &word[lead..];
+
+		let prefix = ["https://", "http://", "www."]
+			.into_iter()
+			.find(|prefix| link.starts_with(prefix));
+		let Some(prefix) = prefix else {
+			continue;
+		};
+
+		// Trailing punctuation is most likely part of the sentence, as are unbalanced parentheses
+		let mut link = link;
+		loop {
+			link = match link.chars().next_back() {
+				Some('?' | '!' | '.' | ',' | ':' | ';' | '*' | '_' | '~' | '\'' | '"') => {
+					&link[..link.len() - 1]
+				}
+				Some(')') if link.matches(')').count() > link.matches('(').count() => {
+					&link[..link.len() - 1]
+				}
+				_ => break,
+			};
+		}
+
+		if link.len() <= prefix.len() {
+			continue;
+		}
+
+		let url = match prefix {
+			"www." => Url::parse(&format!("https://{link}")),
+			_ => Url::parse(link),
+		};
+		if let Ok(url) = url {
+			links.push((start + lead..start + lead + link.len(), url));
+		}
+	}
+
+	links
+}
+
+/// Parses a `{width=...}` attribute at the start of the text, in pixels or as a percentage
+fn width(text: &str) -> Option<(Width, &str)> {
+	let (attributes, rest) = text.strip_prefix('{')?.split_once('}')?;
+
+	let width = attributes.split_whitespace().find_map(|attribute| {
+		let value = attribute.strip_prefix("width=")?.trim_matches('"');
+
+		match value.strip_suffix('%') {
+			Some(percent) => Some(Width::Percent(percent.parse::<u16>().ok()?.clamp(1, 100))),
+			None => Some(Width::Pixels(
+				value.strip_suffix("px").unwrap_or(value).parse().ok()?,
+			)),
+		}
+	})?;
+
+	Some((width, rest))
+}
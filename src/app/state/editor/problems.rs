@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use cosmic::{
+	Element,
+	iced::Length,
+	iced_widget::{Column, scrollable, text},
+	widget::button,
+};
+
+use crate::{
+	app::message::Message,
+	md::lint::{Diagnostic, Rule},
+	trans,
+	utils::links::{BrokenLink, Reason},
+};
+
+/// Lists the problems found by the linter and the broken links found by the link checker, jumping
+/// to one when it is pressed
+pub fn view<'a>(
+	diagnostics: &[Diagnostic],
+	broken_links: &[BrokenLink],
+	document: Option<&Path>,
+	size: f32,
+) -> Element<'a, Message> {
+	if diagnostics.is_empty() && broken_links.is_empty() {
+		return text(trans!("no_problems")).size(size).into();
+	}
+
+	let problems = diagnostics
+		.iter()
+		.map(|diagnostic| -> Element<'a, Message> {
+			let label = format!(
+				"{}:{}  {}",
+				diagnostic.line + 1,
+				diagnostic.columns.start + 1,
+				message(diagnostic)
+			);
+
+			button::text(label)
+				.font_size(size as u16)
+				.on_press(Message::GoTo(diagnostic.line, diagnostic.columns.start))
+				.into()
+		});
+
+	// Broken links in other documents open them
+	let links = broken_links.iter().map(|link| -> Element<'a, Message> {
+		let (line, column) = (link.line, link.columns.start);
+
+		let (label, message) = match &link.path {
+			Some(path) if Some(path.as_path()) != document => (
+				format!(
+					"{}:{}:{}  {}",
+					path.display(),
+					line + 1,
+					column + 1,
+					link_message(link)
+				),
+				Message::OpenAt(path.clone(), line, column),
+			),
+			_ => (
+				format!("{}:{}  {}", line + 1, column + 1, link_message(link)),
+				Message::GoTo(line, column),
+			),
+		};
+
+		button::text(label)
+			.font_size(size as u16)
+			.on_press(message)
+			.into()
+	});
+
+	let problems = problems.chain(links).collect::<Vec<Element<'a, Message>>>();
+
+	scrollable(Column::with_children(problems))
+		.width(Length::Fill)
+		.height(size * 10.)
+		.into()
+}
+
+/// Describes the problem
+pub fn message(diagnostic: &Diagnostic) -> String {
+	let detail = diagnostic.detail.clone();
+
+	match diagnostic.rule {
+		Rule::HeadingIncrement => trans!("lint_heading_increment", level = detail),
+		Rule::DuplicateHeading => trans!("lint_duplicate_heading", heading = detail),
+		Rule::BrokenReference => trans!("lint_broken_reference", reference = detail),
+		Rule::MissingAlt => trans!("lint_missing_alt"),
+		Rule::TrailingSpaces => trans!("lint_trailing_spaces"),
+		Rule::ListMarker => trans!("lint_list_marker", marker = detail),
+		Rule::BareUrl => trans!("lint_bare_url", url = detail),
+	}
+}
+
+/// Describes why the link does not lead anywhere
+pub fn link_message(link: &BrokenLink) -> String {
+	let url = link.url.as_str();
+
+	match &link.reason {
+		Reason::MissingFile => trans!("link_missing_file", url = url),
+		Reason::MissingImage => trans!("link_missing_image", url = url),
+		Reason::MissingAnchor => trans!("link_missing_anchor", url = url),
+		Reason::Unreachable(error) => trans!("link_unreachable", url = url, error = error.as_str()),
+	}
+}
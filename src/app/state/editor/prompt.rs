@@ -0,0 +1,54 @@
+use cosmic::{
+	Element,
+	iced::Alignment,
+	iced_widget::{row, text, text_input},
+	widget::button,
+};
+
+use crate::{app::message::Message, trans};
+
+/// What the text entered into the prompt is used for
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Prompt {
+	/// The delimiter the selection is surrounded with
+	Surround,
+	/// The URL the selection links to
+	Link,
+}
+
+/// A single line of input requested by a command, shown above the editor
+pub struct PromptBar {
+	pub prompt: Prompt,
+	pub value: String,
+	pub id: text_input::Id,
+}
+
+impl PromptBar {
+	pub fn new(prompt: Prompt) -> Self {
+		Self {
+			prompt,
+			value: String::new(),
+			id: text_input::Id::unique(),
+		}
+	}
+
+	pub fn view<'a>(&'a self, size: f32) -> Element<'a, Message> {
+		let label = match self.prompt {
+			Prompt::Surround => trans!("surround_prompt"),
+			Prompt::Link => trans!("link_prompt"),
+		};
+
+		row![
+			text(label).size(size),
+			text_input("", &self.value)
+				.id(self.id.clone())
+				.size(size)
+				.on_input(Message::PromptInput)
+				.on_submit(Message::PromptSubmit),
+			button::text(trans!("cancel")).on_press(Message::PromptCancel),
+		]
+		.spacing(size / 2.)
+		.align_y(Alignment::Center)
+		.into()
+	}
+}
@@ -0,0 +1,47 @@
+use cosmic::{
+	Element,
+	iced::Alignment,
+	iced_widget::{Row, text},
+	widget::button,
+};
+
+use crate::{app::message::Message, md::spell::Misspelling, trans};
+
+/// How many suggestions are shown at once
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Suggestions for the misspelled word the cursor is in, shown above the editor
+pub struct Suggestions {
+	pub misspelling: Misspelling,
+	/// None while the dictionary is still looking for them
+	pub suggestions: Option<Vec<String>>,
+}
+
+impl Suggestions {
+	pub fn view<'a>(&'a self, size: f32) -> Element<'a, Message> {
+		let mut row = Row::new()
+			.spacing(size / 2.)
+			.align_y(Alignment::Center)
+			.push(text(self.misspelling.word.as_str()).size(size));
+
+		let suggestions = self.suggestions.as_deref().unwrap_or_default();
+		if self.suggestions.as_ref().is_some_and(Vec::is_empty) {
+			row = row.push(text(trans!("no_suggestions")).size(size));
+		}
+
+		for suggestion in suggestions.iter().take(MAX_SUGGESTIONS) {
+			row = row.push(
+				button::text(suggestion.as_str())
+					.font_size(size as u16)
+					.on_press(Message::ReplaceWord(suggestion.clone())),
+			);
+		}
+
+		row.push(
+			button::text(trans!("add_to_dictionary"))
+				.font_size(size as u16)
+				.on_press(Message::AddToDictionary),
+		)
+		.into()
+	}
+}
@@ -0,0 +1,119 @@
+use cosmic::{
+	Element,
+	iced_widget::{Row, text},
+};
+
+use crate::{app::message::Message, md::stats::Stats, trans};
+
+/// Something that can be shown in the status bar
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Field {
+	Words,
+	Characters,
+	Lines,
+	ReadingTime,
+	/// The words and characters that are selected
+	Selection,
+	/// The line and column of the cursor
+	Cursor,
+	Encoding,
+	LineEnding,
+}
+
+/// How lines end in the file, which is kept when saving it
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum LineEnding {
+	#[default]
+	Lf,
+	CrLf,
+}
+
+/// Everything the status bar can show
+pub struct Status {
+	pub stats: Stats,
+	/// Only set if anything is selected
+	pub selection: Option<Stats>,
+	/// Both start from 1
+	pub cursor: (usize, usize),
+	pub line_ending: LineEnding,
+	pub words_per_minute: usize,
+}
+
+impl Field {
+	pub fn parse(name: &str) -> Option<Self> {
+		match name.to_lowercase().trim() {
+			"words" => Some(Self::Words),
+			"characters" => Some(Self::Characters),
+			"lines" => Some(Self::Lines),
+			"reading_time" => Some(Self::ReadingTime),
+			"selection" => Some(Self::Selection),
+			"cursor" => Some(Self::Cursor),
+			"encoding" => Some(Self::Encoding),
+			"line_ending" => Some(Self::LineEnding),
+			_ => None,
+		}
+	}
+}
+
+impl LineEnding {
+	/// Finds the line ending used by the text, based on its first line
+	pub fn detect(text: &str) -> Self {
+		match text.find('\n') {
+			Some(i) if text[..i].ends_with('\r') => Self::CrLf,
+			_ => Self::Lf,
+		}
+	}
+
+	/// Converts text using '\n' to use this line ending
+	pub fn apply(&self, text: String) -> String {
+		match self {
+			Self::Lf => text,
+			Self::CrLf => text.replace('\n', "\r\n"),
+		}
+	}
+}
+
+impl Status {
+	fn field(&self, field: Field) -> Option<String> {
+		let text = match field {
+			Field::Words => trans!("status_words", count = self.stats.words),
+			Field::Characters => trans!("status_characters", count = self.stats.characters),
+			Field::Lines => trans!("status_lines", count = self.stats.lines),
+			Field::ReadingTime => trans!(
+				"status_reading_time",
+				minutes = self.stats.reading_time(self.words_per_minute)
+			),
+			Field::Selection => {
+				let selection = self.selection?;
+				trans!(
+					"status_selection",
+					words = selection.words,
+					characters = selection.characters
+				)
+			}
+			Field::Cursor => trans!(
+				"status_cursor",
+				line = self.cursor.0,
+				column = self.cursor.1
+			),
+			Field::Encoding => "UTF-8".into(),
+			Field::LineEnding => match self.line_ending {
+				LineEnding::Lf => "LF".into(),
+				LineEnding::CrLf => "CRLF".into(),
+			},
+		};
+
+		Some(text)
+	}
+
+	/// Renders the fields in order, skipping the ones that have nothing to show
+	pub fn view<'a>(&self, fields: &[Field], size: f32) -> Element<'a, Message> {
+		let fields = fields
+			.iter()
+			.filter_map(|field| self.field(*field))
+			.map(|field| text(field).size(size).into())
+			.collect::<Vec<Element<'a, Message>>>();
+
+		Row::with_children(fields).spacing(size * 2.).into()
+	}
+}
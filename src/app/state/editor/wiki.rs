@@ -0,0 +1,113 @@
+//! The note bar shown while the cursor is in a wiki link, and the panel of backlinks
+
+use std::path::PathBuf;
+
+use cosmic::{
+	Element,
+	iced::{Alignment, Length},
+	iced_widget::{Column, Row, scrollable, text},
+	widget::button,
+};
+
+use crate::{
+	app::message::Message,
+	md::{anchor, wiki::WikiLink},
+	trans,
+	utils::notes,
+};
+
+/// How many notes are suggested at once
+const MAX_NOTES: usize = 5;
+
+/// Shown above the editor while the cursor is in a wiki link
+pub enum NoteBar {
+	/// The name of a note is being typed, along with the names of the notes it could be
+	Completion { partial: String, names: Vec<String> },
+	/// The link is finished, and either opens the note or creates it if it does not exist
+	Link { link: WikiLink, exists: bool },
+}
+
+impl NoteBar {
+	/// Suggests the notes whose names contain the text typed so far, starting with those the
+	/// name starts with
+	pub fn completion(partial: &str, notes: &[PathBuf]) -> Self {
+		let lower = partial.to_lowercase();
+
+		let mut names = notes
+			.iter()
+			.map(|path| notes::name(path))
+			.filter(|name| name.to_lowercase().contains(&lower))
+			.collect::<Vec<String>>();
+		names.sort_by_key(|name| !name.to_lowercase().starts_with(&lower));
+		names.dedup();
+
+		Self::Completion {
+			partial: partial.into(),
+			names,
+		}
+	}
+
+	pub fn view<'a>(&'a self, size: f32) -> Element<'a, Message> {
+		let row = Row::new().spacing(size / 2.).align_y(Alignment::Center);
+
+		match self {
+			Self::Completion { partial, names } => {
+				let mut row = row.push(text(format!("[[{partial}")).size(size));
+
+				if names.is_empty() {
+					row = row.push(text(trans!("no_notes")).size(size));
+				}
+
+				for name in names.iter().take(MAX_NOTES) {
+					row = row.push(
+						button::text(name.as_str())
+							.font_size(size as u16)
+							.on_press(Message::CompleteNote(name.clone())),
+					);
+				}
+
+				row.into()
+			}
+
+			Self::Link { link, exists } => {
+				let label = if *exists {
+					trans!("open_note", note = link.note.as_str())
+				} else {
+					trans!("create_note", note = link.note.as_str())
+				};
+
+				row.push(
+					button::text(label)
+						.font_size(size as u16)
+						.on_press(Message::OpenNote(
+							link.note.clone(),
+							link.anchor.as_deref().map(anchor::slug),
+						)),
+				)
+				.into()
+			}
+		}
+	}
+}
+
+/// Lists the notes linking to the document, opening one when it is pressed
+pub fn backlinks<'a>(backlinks: &[PathBuf], size: f32) -> Element<'a, Message> {
+	if backlinks.is_empty() {
+		return text(trans!("no_backlinks")).size(size).into();
+	}
+
+	let backlinks = backlinks
+		.iter()
+		.map(|path| {
+			button::text(notes::name(path))
+				.font_size(size as u16)
+				.on_press(Message::OpenLink(path.clone(), None))
+				.into()
+		})
+		.collect::<Vec<Element<'a, Message>>>();
+
+	scrollable(Column::with_children(backlinks))
+		.width(size * 15.)
+		.height(Length::Fill)
+		.into()
+}
@@ -0,0 +1,154 @@
+//! Subcommands that run without opening the app
+
+use std::{fs, path::PathBuf};
+
+use clap::ArgMatches;
+use tracing::error;
+
+use crate::{
+	app::state::editor::problems,
+	md,
+	utils::{cfg::script::ScriptCfg, links::Checker, notes},
+};
+
+/// Runs the subcommand passed to the app, if there is one, returning the exit code
+pub fn run(args: &ArgMatches, cfg: &ScriptCfg) -> Option<i32> {
+	match args.subcommand()? {
+		("fmt", args) => Some(fmt(args, cfg)),
+		("lint", args) => Some(lint(args, cfg)),
+		("check-links", args) => Some(check_links(args, cfg)),
+		("export", args) => Some(export(args)),
+		_ => None,
+	}
+}
+
+/// Formats every file in place, or lists the ones that would change when checking
+fn fmt(args: &ArgMatches, cfg: &ScriptCfg) -> i32 {
+	let check = args.get_flag("check");
+	let mut failed = false;
+
+	for (path, text) in files(args, &mut failed) {
+		let formatted = md::fmt::format(&text, &cfg.format_options);
+		if formatted == text {
+			continue;
+		}
+
+		if check {
+			println!("{}", path.display());
+			failed = true;
+		} else if let Err(e) = fs::write(&path, formatted) {
+			error!("Could not write {path:?}: {e}");
+			failed = true;
+		} else {
+			println!("Formatted {}", path.display());
+		}
+	}
+
+	i32::from(failed)
+}
+
+/// Lists the problems found in every file, failing if there are any
+fn lint(args: &ArgMatches, cfg: &ScriptCfg) -> i32 {
+	let mut failed = false;
+
+	for (path, text) in files(args, &mut failed) {
+		let rules = cfg.flags.lint_rules(Some(&path));
+
+		for diagnostic in md::lint::lint(&text, &rules) {
+			println!(
+				"{}:{}:{}: {} [{}]",
+				path.display(),
+				diagnostic.line + 1,
+				diagnostic.columns.start + 1,
+				problems::message(&diagnostic),
+				diagnostic.rule.name()
+			);
+			failed = true;
+		}
+	}
+
+	i32::from(failed)
+}
+
+/// Lists the links that do not lead anywhere in every file, failing if there are any.
+///
+/// Directories are searched for files, and the current directory is checked if nothing is passed.
+fn check_links(args: &ArgMatches, cfg: &ScriptCfg) -> i32 {
+	let external = args.get_flag("external") || cfg.flags.check_external_links;
+	let mut checker = Checker::new(external);
+	let mut failed = false;
+
+	let paths = match args.get_many::<PathBuf>("PATHS") {
+		Some(paths) => paths.cloned().collect(),
+		None => vec![PathBuf::from(".")],
+	};
+	let paths = paths
+		.into_iter()
+		.flat_map(|path| {
+			if path.is_dir() {
+				notes::list(&path)
+			} else {
+				vec![path]
+			}
+		})
+		.collect::<Vec<PathBuf>>();
+
+	for path in paths {
+		let text = match fs::read_to_string(&path) {
+			Ok(text) => text,
+			Err(e) => {
+				error!("Could not read {path:?}: {e}");
+				failed = true;
+				continue;
+			}
+		};
+
+		for link in checker.check(Some(&path), &text) {
+			println!(
+				"{}:{}:{}: {}",
+				path.display(),
+				link.line + 1,
+				link.columns.start + 1,
+				problems::link_message(&link)
+			);
+			failed = true;
+		}
+	}
+
+	i32::from(failed)
+}
+
+/// Writes every file as an HTML page next to it
+fn export(args: &ArgMatches) -> i32 {
+	let mut failed = false;
+
+	for (path, text) in files(args, &mut failed) {
+		let title = path.file_stem().unwrap_or_default().to_string_lossy();
+		let html = path.with_extension("html");
+
+		if let Err(e) = fs::write(&html, md::html::export(&text, &title)) {
+			error!("Could not write {html:?}: {e}");
+			failed = true;
+		} else {
+			println!("Exported {}", html.display());
+		}
+	}
+
+	i32::from(failed)
+}
+
+/// Reads the files passed to the subcommand, failing if any of them cannot be read
+fn files(args: &ArgMatches, failed: &mut bool) -> Vec<(PathBuf, String)> {
+	args.get_many::<PathBuf>("FILES")
+		.into_iter()
+		.flatten()
+		.filter_map(|path| match fs::read_to_string(path) {
+			Ok(text) => Some((path.clone(), text)),
+			Err(e) => {
+				error!("Could not read {path:?}: {e}");
+				*failed = true;
+				None
+			}
+		})
+		.collect()
+}
@@ -5,6 +5,8 @@ use tikv_jemallocator::Jemalloc;
 use utils::init;
 
 mod app;
+mod cli;
+mod md;
 mod trans;
 mod utils;
 
@@ -20,6 +22,10 @@ fn main() -> utils::AppResult<()> {
 	let settings = Settings::default();
 	let flags = init::cfg(&args);
 
+	if let Some(code) = cli::run(&args, &flags) {
+		std::process::exit(code);
+	}
+
 	// Testing the Rhai callback system
 	if let Err(e) = flags.call_rhai_fn::<()>(flags.flags.callback.clone(), ()) {
 		tracing::error!("{e}");
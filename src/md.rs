@@ -0,0 +1,42 @@
+//! Markdown processing that does not depend on the UI
+
+use std::path::Path;
+
+use pulldown_cmark::Options;
+
+pub mod anchor;
+pub mod block;
+pub mod fmt;
+pub mod format;
+pub mod front_matter;
+pub mod html;
+pub mod links;
+pub mod lint;
+pub mod list;
+pub mod math;
+pub mod pairs;
+pub mod reflow;
+pub mod spell;
+pub mod stats;
+pub mod table;
+pub mod wiki;
+
+/// The extensions enabled whenever a document is parsed, so that everything agrees on how it renders
+pub const EXTENSIONS: Options = Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+	.union(Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS)
+	.union(Options::ENABLE_TABLES)
+	.union(Options::ENABLE_STRIKETHROUGH)
+	.union(Options::ENABLE_TASKLISTS)
+	.union(Options::ENABLE_FOOTNOTES)
+	.union(Options::ENABLE_GFM)
+	.union(Options::ENABLE_MATH);
+
+/// The file extensions of Markdown documents
+const FILE_EXTENSIONS: [&str; 3] = ["md", "markdown", "mdown"];
+
+/// Checks if the file is a Markdown document by its extension
+pub fn is_markdown(path: &Path) -> bool {
+	path.extension()
+		.and_then(|ext| ext.to_str())
+		.is_some_and(|ext| FILE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
@@ -0,0 +1,70 @@
+//! The anchors of headings, which links point to with `#anchor`
+
+use std::collections::HashMap;
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+/// The anchor GitHub gives a heading, which is its text in lowercase with spaces replaced by
+/// dashes and other punctuation left out
+pub fn slug(heading: &str) -> String {
+	heading
+		.trim()
+		.to_lowercase()
+		.chars()
+		.filter_map(|ch| match ch {
+			' ' => Some('-'),
+			'-' | '_' => Some(ch),
+			ch if ch.is_alphanumeric() => Some(ch),
+			_ => None,
+		})
+		.collect()
+}
+
+/// The anchor of every heading, along with the line it is on.
+///
+/// Headings with the same text get a number after their anchor, like on GitHub.
+pub fn headings(text: &str) -> Vec<(String, usize)> {
+	let mut anchors = vec![];
+	let mut counts: HashMap<String, usize> = HashMap::new();
+	// The start of the heading being read, along with its text
+	let mut heading: Option<(usize, String)> = None;
+
+	for (event, range) in Parser::new_ext(text, super::EXTENSIONS).into_offset_iter() {
+		match event {
+			Event::Start(Tag::Heading { .. }) => heading = Some((range.start, String::new())),
+			Event::Text(part) | Event::Code(part) => {
+				if let Some((_, text)) = &mut heading {
+					text.push_str(&part);
+				}
+			}
+			Event::End(TagEnd::Heading(_)) => {
+				let Some((start, heading)) = heading.take() else {
+					continue;
+				};
+
+				let slug = slug(&heading);
+				let count = counts.entry(slug.clone()).or_default();
+				let anchor = match *count {
+					0 => slug,
+					n => format!("{slug}-{n}"),
+				};
+				*count += 1;
+
+				anchors.push((anchor, text[..start].matches('\n').count()));
+			}
+			_ => (),
+		}
+	}
+
+	anchors
+}
+
+/// The line of the heading with the anchor, if there is one
+pub fn find(text: &str, anchor: &str) -> Option<usize> {
+	let anchor = anchor.replace("%20", "-").to_lowercase();
+
+	headings(text)
+		.into_iter()
+		.find(|(heading, _)| *heading == anchor)
+		.map(|(_, line)| line)
+}
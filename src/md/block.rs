@@ -0,0 +1,396 @@
+//! Line-based parsing of the block structure of a document.
+//!
+//! Only as precise as editing commands need it to be, so it does not cover every corner of
+//! CommonMark. The preview uses a proper parser instead.
+
+use std::ops::Range;
+
+/// What a single line of a document belongs to
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Kind {
+	Blank,
+	/// Text that can be freely rewrapped
+	Prose,
+	Heading,
+	/// The opening or closing line of a fenced code block
+	Fence,
+	Code,
+	Table,
+	Rule,
+	Html,
+	FrontMatter,
+}
+
+/// A line split into its container markers and text
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Line<'a> {
+	/// Blockquote markers, including the space after them
+	pub quote: &'a str,
+	/// Whitespace before the list marker or text
+	pub indent: &'a str,
+	/// The list marker, including the task box and the space after them
+	pub marker: &'a str,
+	pub list: Option<List>,
+	pub text: &'a str,
+}
+
+/// The list item a line starts
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct List {
+	pub bullet: Bullet,
+	/// Whether the task box is checked, if the item has one
+	pub task: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Bullet {
+	/// Either '-', '*' or '+'
+	Unordered(char),
+	/// The number followed by either '.' or ')'
+	Ordered(u64, char),
+}
+
+impl Line<'_> {
+	/// How many blockquotes the line is nested in
+	pub fn depth(&self) -> usize {
+		self.quote.matches('>').count()
+	}
+
+	/// Everything before the text
+	pub fn prefix(&self) -> String {
+		format!("{}{}{}", self.quote, self.indent, self.marker)
+	}
+
+	/// The prefix of the lines following this one, with the list marker replaced by spaces
+	pub fn continuation(&self) -> String {
+		format!(
+			"{}{}{}",
+			self.quote,
+			self.indent,
+			" ".repeat(self.marker.chars().count())
+		)
+	}
+}
+
+impl List {
+	/// The marker of the item following this one
+	pub fn next(self) -> Self {
+		Self {
+			bullet: match self.bullet {
+				Bullet::Ordered(number, delimiter) => Bullet::Ordered(number + 1, delimiter),
+				unordered => unordered,
+			},
+			task: self.task.map(|_| false),
+		}
+	}
+
+	/// Renders the marker, followed by a space
+	pub fn marker(&self) -> String {
+		let bullet = match self.bullet {
+			Bullet::Unordered(bullet) => bullet.to_string(),
+			Bullet::Ordered(number, delimiter) => format!("{number}{delimiter}"),
+		};
+
+		match self.task {
+			Some(true) => format!("{bullet} [x] "),
+			Some(false) => format!("{bullet} [ ] "),
+			None => format!("{bullet} "),
+		}
+	}
+}
+
+/// Splits a line into its container markers and text
+pub fn split(line: &str) -> Line<'_> {
+	let quote_len = quote_len(line);
+	let (quote, rest) = line.split_at(quote_len);
+
+	let indent_len = rest.len() - rest.trim_start().len();
+	let (indent, rest) = rest.split_at(indent_len);
+
+	let (list, marker_len) = match list_marker(rest) {
+		Some((list, len)) if !is_rule(rest) => (Some(list), len),
+		_ => (None, 0),
+	};
+	let (marker, text) = rest.split_at(marker_len);
+
+	Line {
+		quote,
+		indent,
+		marker,
+		list,
+		text,
+	}
+}
+
+/// The text of a line without any blockquote markers or indentation
+pub fn strip(line: &str) -> &str {
+	line[quote_len(line)..].trim_start()
+}
+
+fn quote_len(line: &str) -> usize {
+	let mut len = 0;
+
+	loop {
+		let rest = &line[len..];
+		let trimmed = rest.trim_start_matches(' ');
+		let spaces = rest.len() - trimmed.len();
+
+		if spaces > 3 || !trimmed.starts_with('>') {
+			return len;
+		}
+
+		len += spaces + 1;
+		if line[len..].starts_with(' ') {
+			len += 1;
+		}
+	}
+}
+
+/// Parses a list marker at the start of the text, returning it with its length in bytes
+fn list_marker(text: &str) -> Option<(List, usize)> {
+	let mut chars = text.char_indices();
+	let (_, first) = chars.next()?;
+
+	let (bullet, len) = match first {
+		'-' | '*' | '+' => (Bullet::Unordered(first), 1),
+		'0'..='9' => {
+			let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+			let delimiter = text[digits..].chars().next()?;
+
+			if digits > 9 || !matches!(delimiter, '.' | ')') {
+				return None;
+			}
+			(
+				Bullet::Ordered(text[..digits].parse().ok()?, delimiter),
+				digits + 1,
+			)
+		}
+		_ => return None,
+	};
+
+	let rest = &text[len..];
+	if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+		return None;
+	}
+
+	let spaces = rest.len() - rest.trim_start().len();
+	let mut len = len + spaces;
+
+	let task = match &text[len..] {
+		rest if rest.starts_with("[ ]") => Some(false),
+		rest if rest.starts_with("[x]") || rest.starts_with("[X]") => Some(true),
+		_ => None,
+	};
+	if task.is_some() {
+		let rest = &text[len + 3..];
+		if rest.is_empty() || rest.starts_with(' ') {
+			len += 3 + (rest.len() - rest.trim_start().len());
+		}
+	}
+
+	Some((List { bullet, task }, len))
+}
+
+/// Classifies every line of the text, split by '\n'
+pub fn classify(text: &str) -> Vec<Kind> {
+	let lines = text.split('\n').collect::<Vec<&str>>();
+	let front_matter = front_matter_end(&lines);
+
+	let mut kinds = Vec::with_capacity(lines.len());
+	let mut fence: Option<(char, usize)> = None;
+	let mut in_list = false;
+
+	for (i, line) in lines.iter().enumerate() {
+		if front_matter.is_some_and(|end| i <= end) {
+			kinds.push(Kind::FrontMatter);
+			continue;
+		}
+
+		let trimmed = strip(line);
+		let previous = kinds.last().copied().unwrap_or(Kind::Blank);
+
+		if let Some((ch, len)) = fence {
+			if fence_len(trimmed, ch).is_some_and(|closing| closing >= len)
+				&& trimmed.trim_start_matches(ch).trim().is_empty()
+			{
+				fence = None;
+				kinds.push(Kind::Fence);
+			} else {
+				kinds.push(Kind::Code);
+			}
+			continue;
+		}
+
+		let parts = split(line);
+
+		let kind = if trimmed.is_empty() {
+			Kind::Blank
+		} else if let Some(open) = opens_fence(trimmed) {
+			fence = Some(open);
+			in_list = false;
+			Kind::Fence
+		} else if is_rule(trimmed) {
+			in_list = false;
+			Kind::Rule
+		} else if is_heading(trimmed) {
+			in_list = false;
+			Kind::Heading
+		} else if matches!(previous, Kind::Blank | Kind::Code)
+			&& !in_list
+			&& indent_width(parts.indent) >= 4
+		{
+			Kind::Code
+		} else if previous == Kind::Html || (previous != Kind::Prose && trimmed.starts_with('<')) {
+			Kind::Html
+		} else {
+			if parts.list.is_some() {
+				in_list = true;
+			} else if previous == Kind::Blank && parts.indent.is_empty() {
+				in_list = false;
+			}
+			Kind::Prose
+		};
+
+		kinds.push(kind);
+	}
+
+	for i in 1..lines.len() {
+		// Setext headings
+		if kinds[i - 1] == Kind::Prose
+			&& matches!(kinds[i], Kind::Prose | Kind::Rule)
+			&& split(lines[i - 1]).list.is_none()
+			&& is_setext_underline(strip(lines[i]))
+		{
+			kinds[i - 1] = Kind::Heading;
+			kinds[i] = Kind::Heading;
+		}
+
+		// Tables start with a header row, followed by a delimiter row
+		if kinds[i - 1] == Kind::Prose
+			&& kinds[i] == Kind::Prose
+			&& lines[i - 1].contains('|')
+			&& is_delimiter_row(strip(lines[i]))
+		{
+			kinds[i - 1] = Kind::Table;
+
+			for j in i..lines.len() {
+				if kinds[j] != Kind::Prose || !lines[j].contains('|') {
+					break;
+				}
+				kinds[j] = Kind::Table;
+			}
+		}
+	}
+
+	kinds
+}
+
+/// Groups consecutive lines of prose into paragraphs, returning the lines each one spans
+pub fn paragraphs(lines: &[&str], kinds: &[Kind]) -> Vec<Range<usize>> {
+	let mut paragraphs: Vec<Range<usize>> = vec![];
+
+	for (i, (line, kind)) in lines.iter().zip(kinds).enumerate() {
+		if *kind != Kind::Prose {
+			continue;
+		}
+
+		let continues = i > 0
+			&& kinds[i - 1] == Kind::Prose
+			&& !has_hard_break(lines[i - 1])
+			&& split(line).list.is_none()
+			&& split(line).depth() == split(lines[i - 1]).depth();
+
+		match paragraphs.last_mut() {
+			Some(paragraph) if continues => paragraph.end = i + 1,
+			_ => paragraphs.push(i..i + 1),
+		}
+	}
+
+	paragraphs
+}
+
+/// Checks if the line ends with two spaces or a backslash
+pub fn has_hard_break(line: &str) -> bool {
+	line.ends_with("  ") || line.ends_with('\\')
+}
+
+/// Checks if a word would start a new block if it was placed at the start of a line
+pub fn starts_block(word: &str) -> bool {
+	is_heading(word)
+		|| word.starts_with('>')
+		|| list_marker(word).is_some()
+		|| is_rule(word)
+		|| opens_fence(word).is_some()
+}
+
+/// The line closing the front matter at the start of the document, if there is any
+pub fn front_matter_end(lines: &[&str]) -> Option<usize> {
+	let closing = front_matter_closing(lines.first()?)?;
+
+	lines
+		.iter()
+		.skip(1)
+		.position(|line| closing.contains(&line.trim_end()))
+		.map(|i| i + 1)
+}
+
+/// The lines that can close front matter opened by the first line of the document, if it opens
+/// any
+pub fn front_matter_closing(first: &str) -> Option<&'static [&'static str]> {
+	match first.trim_end() {
+		"---" => Some(&["---", "..."]),
+		"+++" => Some(&["+++"]),
+		_ => None,
+	}
+}
+
+pub fn is_heading(text: &str) -> bool {
+	let hashes = text.len() - text.trim_start_matches('#').len();
+	let rest = &text[hashes..];
+	(1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with([' ', '\t']))
+}
+
+pub fn is_rule(text: &str) -> bool {
+	let chars = text
+		.chars()
+		.filter(|ch| !ch.is_whitespace())
+		.collect::<Vec<char>>();
+	chars.len() >= 3
+		&& matches!(chars[0], '-' | '*' | '_')
+		&& chars.iter().all(|ch| *ch == chars[0])
+}
+
+fn is_setext_underline(text: &str) -> bool {
+	let text = text.trim_end();
+	!text.is_empty() && (text.chars().all(|ch| ch == '=') || text.chars().all(|ch| ch == '-'))
+}
+
+pub fn is_delimiter_row(text: &str) -> bool {
+	text.contains('|')
+		&& text.contains('-')
+		&& text
+			.chars()
+			.all(|ch| matches!(ch, '|' | ':' | '-' | ' ' | '\t'))
+}
+
+/// The character and length of the fence opening a code block
+pub fn opens_fence(text: &str) -> Option<(char, usize)> {
+	['`', '~'].into_iter().find_map(|ch| {
+		let len = fence_len(text, ch)?;
+		// Backtick fences cannot contain backticks in their info string
+		(ch == '~' || !text[len..].contains('`')).then_some((ch, len))
+	})
+}
+
+fn fence_len(text: &str, ch: char) -> Option<usize> {
+	let len = text.len() - text.trim_start_matches(ch).len();
+	(len >= 3).then_some(len)
+}
+
+/// The width of the indentation in columns, with tabs counting as 4
+pub fn indent_width(indent: &str) -> usize {
+	indent
+		.chars()
+		.map(|ch| if ch == '\t' { 4 } else { 1 })
+		.sum()
+}
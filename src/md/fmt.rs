@@ -0,0 +1,456 @@
+//! Normalizes the style of a document without changing how it renders
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+use super::{
+	block::{self, Kind},
+	table,
+};
+
+/// The style a document is formatted to
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Options {
+	pub heading: Heading,
+	/// Either '-', '*' or '+'
+	pub bullet: char,
+	/// Either '*' or '_'
+	pub emphasis: char,
+	/// Either '*' or '_', repeated twice
+	pub strong: char,
+	/// Either '`' or '~'
+	pub fence: char,
+	/// How many blank lines are kept in a row
+	pub blank_lines: usize,
+	pub align_tables: bool,
+	/// Removes whitespace at the end of lines, other than hard breaks
+	pub trim_trailing: bool,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Heading {
+	/// Headings start with '#'
+	Atx,
+	/// Headings of level 1 and 2 are underlined, others start with '#'
+	Setext,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			heading: Heading::Atx,
+			bullet: '-',
+			emphasis: '*',
+			strong: '*',
+			fence: '`',
+			blank_lines: 1,
+			align_tables: true,
+			trim_trailing: true,
+		}
+	}
+}
+
+/// Formats the text, always ending it with a single newline.
+///
+/// Text using CRLF line endings keeps them.
+pub fn format(text: &str, options: &Options) -> String {
+	if text.contains("\r\n") {
+		return format(&text.replace("\r\n", "\n"), options).replace('\n', "\r\n");
+	}
+
+	let text = bullets(&emphasis(text, options), options);
+	// The newline ending the text does not start another line, which would be kept as part of a
+	// code block that is not closed
+	let lines = text
+		.strip_suffix('\n')
+		.unwrap_or(&text)
+		.split('\n')
+		.collect::<Vec<&str>>();
+	let kinds = block::classify(&text);
+
+	let mut out: Vec<String> = vec![];
+	// Whether the next line should be separated from the previous block
+	let mut separate = false;
+	let mut blanks = 0;
+	// Blank lines within a blockquote keep its markers, so that it is not split in two
+	let mut blank = "";
+	let mut i = 0;
+
+	while i < lines.len() {
+		let line = lines[i];
+		let kind = kinds[i];
+		let parts = block::split(line);
+		let top_level = parts.quote.is_empty() && parts.indent.is_empty();
+
+		if kind == Kind::Blank {
+			blanks += 1;
+			blank = parts.quote.trim_end();
+			i += 1;
+			continue;
+		}
+
+		// Headings and code blocks are always surrounded by blank lines, unless they are nested.
+		// Blank lines separate paragraphs, so at least one is always kept
+		let max = options.blank_lines.max(1);
+		let blanks_before = if out.is_empty() {
+			0
+		} else if (separate || matches!(kind, Kind::Heading | Kind::Fence)) && top_level {
+			blanks.clamp(1, max)
+		} else {
+			blanks.min(max)
+		};
+		out.extend((0..blanks_before).map(|_| blank.to_string()));
+		blanks = 0;
+		blank = "";
+		separate = false;
+
+		match kind {
+			Kind::Heading => {
+				let alone = i == 0 || kinds[i - 1] != Kind::Prose;
+				let (heading, len) = heading(&lines[i..], &kinds[i..], alone, options);
+				out.extend(heading);
+				i += len;
+				separate = top_level;
+				continue;
+			}
+
+			Kind::Fence => {
+				let close = (i + 1..lines.len()).find(|j| kinds[*j] == Kind::Fence);
+				let end = close.map_or(lines.len(), |close| close + 1);
+				out.extend(fence(&lines[i..end], close.is_some(), options));
+				i = end;
+				separate = top_level;
+				continue;
+			}
+
+			Kind::Table if options.align_tables => {
+				let end = (i..lines.len())
+					.find(|j| kinds[*j] != Kind::Table)
+					.unwrap_or(lines.len());
+				out.extend(table::align(&lines[i..end]).split('\n').map(String::from));
+				i = end;
+				continue;
+			}
+
+			Kind::Code | Kind::FrontMatter => out.push(line.into()),
+
+			Kind::Prose => {
+				let hard_break = kinds.get(i + 1) == Some(&Kind::Prose) && line.ends_with("  ");
+				let mut line = line.to_string();
+				if options.trim_trailing {
+					line.truncate(line.trim_end().len());
+					if hard_break {
+						line.push_str("  ");
+					}
+				}
+				out.push(line);
+			}
+
+			_ if options.trim_trailing => out.push(line.trim_end().into()),
+			_ => out.push(line.into()),
+		}
+
+		i += 1;
+	}
+
+	let mut out = out.join("\n");
+	out.push('\n');
+	out
+}
+
+/// Formats the heading at the start of the lines, returning it along with how many lines it spanned.
+///
+/// Setext headings can only be turned into ATX ones if they are not the end of a longer paragraph.
+fn heading(lines: &[&str], kinds: &[Kind], alone: bool, options: &Options) -> (Vec<String>, usize) {
+	let parts = block::split(lines[0]);
+	let prefix = format!("{}{}", parts.quote, parts.indent);
+	let content = &lines[0][prefix.len()..];
+
+	let (level, text, len) = if block::is_heading(content) {
+		let level = content.len() - content.trim_start_matches('#').len();
+		let text = content[level..].trim();
+		// Closing hashes are optional, but must be separated from the text
+		let text = match text.trim_end_matches('#') {
+			trimmed if trimmed.is_empty() || trimmed.ends_with([' ', '\t']) => trimmed.trim_end(),
+			_ => text,
+		};
+		(level, text, 1)
+	} else {
+		// Trailing hashes would be taken as closing ones
+		let text = content.trim();
+		if !alone || kinds.get(1) != Some(&Kind::Heading) || text.ends_with('#') {
+			let len = lines.len().min(2);
+			let lines = lines[..len].iter().map(|line| line.trim_end().into());
+			return (lines.collect(), len);
+		}
+		let level = if block::strip(lines[1]).starts_with('=') {
+			1
+		} else {
+			2
+		};
+		(level, text, 2)
+	};
+
+	let heading = match options.heading {
+		Heading::Setext if level <= 2 && !text.is_empty() => {
+			let ch = if level == 1 { '=' } else { '-' };
+			vec![
+				format!("{prefix}{text}"),
+				format!(
+					"{prefix}{}",
+					ch.to_string().repeat(text.chars().count().max(3))
+				),
+			]
+		}
+		_ if text.is_empty() => vec![format!("{prefix}{}", "#".repeat(level))],
+		_ => vec![format!("{prefix}{} {text}", "#".repeat(level))],
+	};
+
+	(heading, len)
+}
+
+/// Changes the fence characters of a code block, unless its content would close the new fence
+fn fence(lines: &[&str], closed: bool, options: &Options) -> Vec<String> {
+	let parts = block::split(lines[0]);
+	let prefix = format!("{}{}", parts.quote, parts.indent);
+	let open = block::strip(lines[0]);
+
+	let Some((ch, len)) = block::opens_fence(open) else {
+		return lines.iter().map(|line| line.to_string()).collect();
+	};
+	let info = open[len..].trim();
+	let code = &lines[1..lines.len() - usize::from(closed)];
+
+	let conflicts = |fence: char| {
+		(fence == '`' && info.contains('`'))
+			|| code
+				.iter()
+				.any(|line| block::strip(line).starts_with(&fence.to_string().repeat(3)))
+	};
+	let ch = if options.fence != ch && !conflicts(options.fence) {
+		options.fence
+	} else {
+		ch
+	};
+	let delimiter = ch.to_string().repeat(len);
+
+	let mut out = vec![format!("{prefix}{delimiter}{info}")];
+	out.extend(code.iter().map(|line| line.to_string()));
+
+	// An unclosed block runs until the end of the document
+	if closed {
+		let last = lines[lines.len() - 1];
+		let prefix = &last[..last.len() - block::strip(last).len()];
+		out.push(format!("{prefix}{delimiter}"));
+	}
+
+	out
+}
+
+/// Replaces the bullets of unordered lists.
+///
+/// Lists right after each other are only kept apart by using different bullets, so a list following
+/// another one keeps its own bullet, or gets another one if it would be the same.
+fn bullets(text: &str, options: &Options) -> String {
+	let parser = Parser::new_ext(text, super::EXTENSIONS).into_offset_iter();
+	let mut bytes = text.as_bytes().to_vec();
+
+	// The bullet of the list each open container ends with, if its last block is one
+	let mut containers: Vec<Option<char>> = vec![None];
+	// Whether each open list is unordered, along with the bullet of the list right before it and
+	// its own bullet, which is decided by its first item
+	let mut lists: Vec<(bool, Option<char>, Option<char>)> = vec![];
+
+	for (event, range) in parser {
+		match event {
+			Event::Start(Tag::List(start)) => {
+				let previous = containers.last_mut().and_then(Option::take);
+				lists.push((start.is_none(), previous, None));
+			}
+
+			Event::End(TagEnd::List(_)) => {
+				let bullet = lists.pop().and_then(|(_, _, bullet)| bullet);
+				if let Some(last) = containers.last_mut() {
+					*last = bullet;
+				}
+			}
+
+			Event::Start(Tag::Item) => {
+				containers.push(None);
+
+				let Some((true, previous, bullet)) = lists.last_mut() else {
+					continue;
+				};
+				let original = bytes[range.start] as char;
+				if !matches!(original, '-' | '*' | '+') {
+					continue;
+				}
+
+				let bullet = *bullet.get_or_insert_with(|| match *previous {
+					None => options.bullet,
+					Some(previous) if original != previous => original,
+					Some(previous) => ['-', '*', '+']
+						.into_iter()
+						.filter(|bullet| *bullet != previous)
+						.min_by_key(|bullet| *bullet != options.bullet)
+						.unwrap_or(original),
+				});
+				bytes[range.start] = bullet as u8;
+			}
+
+			Event::Start(Tag::BlockQuote(_) | Tag::FootnoteDefinition(_)) => {
+				if let Some(last) = containers.last_mut() {
+					*last = None;
+				}
+				containers.push(None);
+			}
+
+			Event::End(TagEnd::Item | TagEnd::BlockQuote(_) | TagEnd::FootnoteDefinition) => {
+				containers.pop();
+			}
+
+			// Any other block in between keeps the lists apart
+			Event::End(_) => (),
+			_ => {
+				if let Some(last) = containers.last_mut() {
+					*last = None;
+				}
+			}
+		}
+	}
+
+	// Only ASCII characters were replaced
+	String::from_utf8(bytes).unwrap_or_else(|_| text.into())
+}
+
+/// Replaces the delimiters of emphasis and strong emphasis.
+///
+/// Underscores only work at the edges of words, so delimiters next to letters keep using asterisks.
+fn emphasis(text: &str, options: &Options) -> String {
+	let parser = Parser::new_ext(text, super::EXTENSIONS).into_offset_iter();
+	let mut bytes = text.as_bytes().to_vec();
+
+	for (event, range) in parser {
+		let (len, ch) = match event {
+			Event::Start(Tag::Emphasis) => (1, options.emphasis),
+			Event::Start(Tag::Strong) => (2, options.strong),
+			_ => continue,
+		};
+
+		let open = range.start..range.start + len;
+		let close = range.end - len..range.end;
+		let delimiters = open.chain(close).collect::<Vec<usize>>();
+		if !delimiters.iter().all(|i| matches!(bytes[*i], b'*' | b'_')) {
+			continue;
+		}
+
+		let flanked = text[..range.start]
+			.chars()
+			.next_back()
+			.is_some_and(char::is_alphanumeric)
+			|| text[range.end..]
+				.chars()
+				.next()
+				.is_some_and(char::is_alphanumeric);
+		if ch == '_' && flanked {
+			continue;
+		}
+
+		for i in delimiters {
+			bytes[i] = ch as u8;
+		}
+	}
+
+	// Only ASCII characters were replaced
+	String::from_utf8(bytes).unwrap_or_else(|_| text.into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Documents covering most of what the formatter changes
+	const DOCUMENTS: [&str; 8] = [
+		"# A\n\n\n\ntext  \nmore\n* a\n* b\n\n- c\n",
+		"Title\n===\n\n__bold__ _em_\n\n~~~rust\nx\n~~~\n",
+		"> a\n>\n> b\n",
+		"| a | b |\n|-|-|\n| ccc | d |\n",
+		"1. one\n2. two\n   * nested\n   * list\n\n+ after\n",
+		"> - quoted\n>\n> * lists\n\nSub\n---\ntext   \n",
+		"- [ ] task\n- [x] done\n\n[link]: https://example.com\n",
+		"",
+	];
+
+	fn format(text: &str) -> String {
+		super::format(text, &Options::default())
+	}
+
+	/// The bullet of every unordered list in the text, in order
+	fn lists(text: &str) -> Vec<char> {
+		Parser::new_ext(text, crate::md::EXTENSIONS)
+			.into_offset_iter()
+			.filter_map(|(event, range)| match event {
+				Event::Start(Tag::List(None)) => text[range].chars().next(),
+				_ => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn normalizes_style() {
+		assert_eq!(
+			format("Title\n===\n\n__bold__ _em_\n\n~~~rust\nx\n~~~\n"),
+			"# Title\n\n**bold** *em*\n\n```rust\nx\n```\n"
+		);
+		assert_eq!(format("# A\n\n\n\ntext"), "# A\n\ntext\n");
+	}
+
+	#[test]
+	fn keeps_crlf() {
+		for document in DOCUMENTS
+			.into_iter()
+			.filter(|document| document.contains('\n'))
+		{
+			let crlf = document.replace('\n', "\r\n");
+			let formatted = format(&crlf);
+
+			assert_eq!(formatted, format(document).replace('\n', "\r\n"));
+			assert!(!formatted.replace("\r\n", "").contains('\n'));
+		}
+	}
+
+	#[test]
+	fn keeps_adjacent_lists_apart() {
+		assert_eq!(format("* a\n* b\n\n- c\n"), "- a\n- b\n\n* c\n");
+		assert_eq!(format("- a\n\n* b\n\n+ c\n"), "- a\n\n* b\n\n+ c\n");
+		assert_eq!(format("* a\n\ntext\n\n* b\n"), "- a\n\ntext\n\n- b\n");
+
+		for document in DOCUMENTS {
+			assert_eq!(lists(&format(document)).len(), lists(document).len());
+		}
+	}
+
+	#[test]
+	fn keeps_quotes_together() {
+		assert_eq!(format("> a\n>\n> b\n"), "> a\n>\n> b\n");
+	}
+
+	#[test]
+	fn is_idempotent() {
+		for document in DOCUMENTS {
+			let formatted = format(document);
+			assert_eq!(format(&formatted), formatted, "{document:?}");
+		}
+	}
+
+	#[test]
+	fn handles_malformed_input() {
+		let documents = [
+			"\n", "\r\n", "\r", "|", "| a |\n|", ">", "> >", "* ", "-\n-", "```", "~~~\n```", "# ",
+			"#", "===", "[x]: ", "- [ ]", "**", "_a*", "1.", "  \t\n\t",
+		];
+		for document in documents {
+			let formatted = format(document);
+			assert_eq!(format(&formatted), formatted, "{document:?}");
+		}
+	}
+}
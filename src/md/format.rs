@@ -0,0 +1,317 @@
+use std::ops::Range;
+
+use super::block::{self, Kind};
+
+/// Inline styles that can be toggled
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Style {
+	Bold,
+	Italic,
+	Strikethrough,
+	Code,
+}
+
+/// The text after a formatting command, along with the byte range that should be selected
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Change {
+	pub text: String,
+	pub selection: Range<usize>,
+}
+
+impl Style {
+	/// The delimiter inserted when adding the style
+	fn delimiter(&self) -> &'static str {
+		match self {
+			Self::Bold => "**",
+			Self::Italic => "*",
+			Self::Strikethrough => "~~",
+			Self::Code => "`",
+		}
+	}
+
+	/// The characters delimiters can be made of
+	fn chars(&self) -> &'static [char] {
+		match self {
+			Self::Bold | Self::Italic => &['*', '_'],
+			Self::Strikethrough => &['~'],
+			Self::Code => &['`'],
+		}
+	}
+
+	/// How many characters to remove from a run of delimiters on both sides to remove the style,
+	/// if the run applies it at all
+	fn removed(&self, run: usize) -> Option<usize> {
+		match self {
+			// A run of 3 is both bold and italic
+			Self::Italic => (run % 2 == 1).then_some(1),
+			Self::Bold => (run >= 2).then_some(2),
+			Self::Strikethrough => (run >= 1).then_some(run.min(2)),
+			Self::Code => (run >= 1).then_some(run),
+		}
+	}
+}
+
+/// Adds or removes the style from the selection, or the word under the cursor if nothing is selected
+pub fn toggle(text: &str, selection: Range<usize>, style: Style) -> Change {
+	let range = if selection.is_empty() {
+		word(text, selection.start)
+	} else {
+		selection
+	};
+	let selected = &text[range.clone()];
+
+	for ch in style.chars() {
+		// Delimiters just outside of the selection
+		let left = run(text[..range.start].chars().rev(), *ch);
+		let right = run(text[range.end..].chars(), *ch);
+
+		if let Some(removed) = style.removed(left.min(right)) {
+			let edits = vec![
+				(range.start - removed..range.start, String::new()),
+				(range.end..range.end + removed, String::new()),
+			];
+			return apply(text, edits, range);
+		}
+
+		// Delimiters at the edges of the selection
+		let left = run(selected.chars(), *ch);
+		let right = run(selected.chars().rev(), *ch);
+
+		let removed = style
+			.removed(left.min(right))
+			.filter(|removed| selected.len() > removed * 2);
+
+		if let Some(removed) = removed {
+			let edits = vec![
+				(range.start..range.start + removed, String::new()),
+				(range.end - removed..range.end, String::new()),
+			];
+			return apply(text, edits, range);
+		}
+	}
+
+	let delimiter = match style {
+		// Code containing backticks needs a longer delimiter
+		Style::Code => {
+			let longest = (1..=selected.len())
+				.rev()
+				.find(|len| selected.contains(&"`".repeat(*len)))
+				.unwrap_or_default();
+			"`".repeat(longest + 1)
+		}
+		_ => style.delimiter().into(),
+	};
+
+	wrap(text, range, &delimiter, &delimiter)
+}
+
+/// Turns the selection, or the word under the cursor, into a link to the URL
+pub fn link(text: &str, selection: Range<usize>, url: &str) -> Change {
+	let range = if selection.is_empty() {
+		word(text, selection.start)
+	} else {
+		selection
+	};
+
+	wrap(text, range, "[", &format!("]({url})"))
+}
+
+/// Removes the link the cursor is in, keeping its text.
+///
+/// Returns None if the cursor is not in a link.
+pub fn unlink(text: &str, cursor: usize) -> Option<Change> {
+	let start = text[..cursor].rfind('\n').map_or(0, |i| i + 1);
+	let end = text[cursor..].find('\n').map_or(text.len(), |i| cursor + i);
+	let line = &text[start..end];
+
+	line.match_indices('[')
+		.map(|(i, _)| start + i)
+		.filter(|open| *open <= cursor && !text[..*open].ends_with('!'))
+		.filter_map(|open| {
+			let middle = open + text[open..end].find("](")?;
+			let close = middle + text[middle..end].find(')')?;
+			(cursor <= close).then_some((open, middle, close))
+		})
+		.next_back()
+		.map(|(open, middle, close)| {
+			let edits = vec![
+				(open..open + 1, String::new()),
+				(middle..close + 1, String::new()),
+			];
+			apply(text, edits, open + 1..middle)
+		})
+}
+
+/// Wraps the selected lines in a code block, or removes the one they are in
+pub fn toggle_code_block(text: &str, selection: Range<usize>) -> Change {
+	let lines = text.split('\n').collect::<Vec<&str>>();
+	let kinds = block::classify(text);
+
+	let first = line_of(text, selection.start);
+	let mut last = line_of(text, selection.end);
+	// A selection ending at the start of a line does not include it
+	if last > first && text[..selection.end].ends_with('\n') {
+		last -= 1;
+	}
+
+	let in_block = kinds[first..=last]
+		.iter()
+		.all(|kind| matches!(kind, Kind::Code | Kind::Fence));
+	let open = (0..=first).rev().find(|i| kinds[*i] == Kind::Fence);
+	let close = (last..lines.len()).find(|i| kinds[*i] == Kind::Fence && Some(*i) != open);
+
+	if let (true, Some(open), Some(close)) = (in_block, open, close) {
+		let open_start = line_start(&lines, open);
+		let close_start = line_start(&lines, close);
+
+		let edits = vec![
+			(
+				open_start..open_start + lines[open].len() + 1,
+				String::new(),
+			),
+			(
+				close_start - 1..close_start + lines[close].len(),
+				String::new(),
+			),
+		];
+		return apply(text, edits, selection);
+	}
+
+	let start = line_start(&lines, first);
+	let end = line_start(&lines, last) + lines[last].len();
+
+	let edits = vec![(start..start, "```\n".into()), (end..end, "\n```".into())];
+	apply(text, edits, start..end)
+}
+
+/// Changes the heading level of the selected lines by the amount, turning paragraphs into headings
+/// and headings below level 1 back into paragraphs
+pub fn shift_heading(text: &str, selection: Range<usize>, amount: isize) -> Change {
+	let lines = text.split('\n').collect::<Vec<&str>>();
+	let kinds = block::classify(text);
+
+	let first = line_of(text, selection.start);
+	let last = line_of(text, selection.end);
+
+	let edits = (first..=last)
+		.filter(|i| matches!(kinds[*i], Kind::Prose | Kind::Heading))
+		.filter_map(|i| {
+			let parts = block::split(lines[i]);
+			let content = &lines[i][parts.quote.len()..];
+
+			// Setext headings and list items are left alone
+			if parts.list.is_some() || (kinds[i] == Kind::Heading && !block::is_heading(content)) {
+				return None;
+			}
+
+			let (hashes, spaces) = if block::is_heading(content) {
+				let hashes = content.len() - content.trim_start_matches('#').len();
+				let spaces = content[hashes..].len() - content[hashes..].trim_start().len();
+				(hashes, spaces)
+			} else {
+				(0, 0)
+			};
+
+			let new = (hashes as isize + amount).clamp(0, 6) as usize;
+			if new == hashes {
+				return None;
+			}
+
+			let start = line_start(&lines, i) + parts.quote.len();
+			let prefix = match new {
+				0 => String::new(),
+				new => format!("{} ", "#".repeat(new)),
+			};
+			Some((start..start + hashes + spaces, prefix))
+		})
+		.collect();
+
+	apply(text, edits, selection)
+}
+
+/// Places the delimiters around the range, selecting the text between them
+fn wrap(text: &str, range: Range<usize>, open: &str, close: &str) -> Change {
+	if range.is_empty() {
+		let cursor = range.start + open.len();
+		let edits = vec![(range.clone(), format!("{open}{close}"))];
+		return Change {
+			selection: cursor..cursor,
+			..apply(text, edits, range)
+		};
+	}
+
+	let edits = vec![
+		(range.start..range.start, open.into()),
+		(range.end..range.end, close.into()),
+	];
+	apply(text, edits, range)
+}
+
+/// The range of the word at the byte offset, which is empty if there is none
+fn word(text: &str, offset: usize) -> Range<usize> {
+	let is_word = |ch: char| ch.is_alphanumeric() || ch == '\'' || ch == '-';
+
+	let start = text[..offset]
+		.char_indices()
+		.rev()
+		.take_while(|(_, ch)| is_word(*ch))
+		.last()
+		.map_or(offset, |(i, _)| i);
+	let end = text[offset..]
+		.char_indices()
+		.find(|(_, ch)| !is_word(*ch))
+		.map_or(text.len(), |(i, _)| offset + i);
+
+	start..end
+}
+
+/// How many times the character is repeated at the start of the iterator
+fn run(chars: impl Iterator<Item = char>, ch: char) -> usize {
+	chars.take_while(|c| *c == ch).count()
+}
+
+fn line_of(text: &str, offset: usize) -> usize {
+	text[..offset].matches('\n').count()
+}
+
+fn line_start(lines: &[&str], line: usize) -> usize {
+	lines[..line].iter().map(|line| line.len() + 1).sum()
+}
+
+/// Replaces byte ranges of the text, moving the selection along with the text around it.
+///
+/// The edits must be sorted and must not overlap. Text inserted at the start of the selection
+/// is placed before it, and text inserted at its end is placed after it.
+fn apply(text: &str, edits: Vec<(Range<usize>, String)>, selection: Range<usize>) -> Change {
+	let map = |offset: usize, is_end: bool| {
+		let mut mapped = offset as isize;
+
+		for (range, insert) in &edits {
+			let before =
+				range.end < offset || (range.end == offset && (!range.is_empty() || !is_end));
+
+			if before {
+				mapped += insert.len() as isize - range.len() as isize;
+			} else if range.start < offset {
+				// Offsets within removed text end up at the start of the edit
+				mapped -= (offset - range.start) as isize;
+			}
+		}
+
+		mapped as usize
+	};
+
+	let mut out = String::with_capacity(text.len());
+	let mut next = 0;
+	for (range, insert) in &edits {
+		out.push_str(&text[next..range.start]);
+		out.push_str(insert);
+		next = range.end;
+	}
+	out.push_str(&text[next..]);
+
+	Change {
+		selection: map(selection.start, false)..map(selection.end, true),
+		text: out,
+	}
+}
@@ -0,0 +1,141 @@
+//! The YAML or TOML fields at the start of a document
+
+use std::fmt::Display;
+
+use super::block;
+
+/// The value of a field
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Value {
+	Text(String),
+	List(Vec<String>),
+}
+
+/// The fields of the front matter, in the order they are written in
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FrontMatter {
+	pub fields: Vec<(String, Value)>,
+}
+
+impl FrontMatter {
+	pub fn get(&self, key: &str) -> Option<&Value> {
+		self.fields
+			.iter()
+			.find(|(field, _)| field == key)
+			.map(|(_, value)| value)
+	}
+
+	/// The value of a field, if it is text that is not empty
+	pub fn text(&self, key: &str) -> Option<&str> {
+		match self.get(key)? {
+			Value::Text(text) if !text.is_empty() => Some(text),
+			_ => None,
+		}
+	}
+}
+
+impl Display for Value {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Text(text) => f.write_str(text),
+			Self::List(items) => f.write_str(&items.join(", ")),
+		}
+	}
+}
+
+/// The text of the front matter at the start of the text, including its delimiters, or nothing if
+/// there is none.
+///
+/// Only the lines up to the end of the front matter are read.
+pub fn source(text: &str) -> &str {
+	let mut lines = text.split_inclusive('\n');
+	let Some(first) = lines.next() else {
+		return "";
+	};
+	let Some(closing) = block::front_matter_closing(first) else {
+		return "";
+	};
+
+	let mut end = first.len();
+	for line in lines {
+		end += line.len();
+		if closing.contains(&line.trim_end()) {
+			return &text[..end];
+		}
+	}
+
+	""
+}
+
+/// Parses the front matter at the start of the text.
+///
+/// Only the subset of YAML and TOML that notes tend to use is understood: a field on each line,
+/// with lists written as `[a, b]` or as YAML items on the lines after the key. Nested fields are
+/// left out.
+pub fn parse(text: &str) -> FrontMatter {
+	let lines = text.split('\n').collect::<Vec<&str>>();
+	let Some(end) = block::front_matter_end(&lines) else {
+		return FrontMatter::default();
+	};
+
+	let mut fields: Vec<(String, Value)> = vec![];
+
+	for line in &lines[1..end] {
+		let trimmed = line.trim();
+		if trimmed.is_empty() || trimmed.starts_with('#') {
+			continue;
+		}
+
+		// YAML items belong to the field before them
+		if let Some(item) = trimmed.strip_prefix('-') {
+			if let Some((_, value)) = fields.last_mut() {
+				match value {
+					Value::List(items) => items.push(unquote(item)),
+					Value::Text(text) if text.is_empty() => {
+						*value = Value::List(vec![unquote(item)])
+					}
+					Value::Text(_) => (),
+				}
+			}
+			continue;
+		}
+
+		// TOML tables start the nested fields
+		if trimmed.starts_with('[') {
+			break;
+		}
+		if line.starts_with([' ', '\t']) {
+			continue;
+		}
+
+		let Some((key, value)) = line.split_once([':', '=']) else {
+			continue;
+		};
+		fields.push((unquote(key), parse_value(value.trim())));
+	}
+
+	// Fields with nested fields are left out along with them
+	fields.retain(|(_, value)| *value != Value::Text(String::new()));
+
+	FrontMatter { fields }
+}
+
+fn parse_value(text: &str) -> Value {
+	match text
+		.strip_prefix('[')
+		.and_then(|text| text.strip_suffix(']'))
+	{
+		Some(items) => Value::List(
+			items
+				.split(',')
+				.map(unquote)
+				.filter(|item| !item.is_empty())
+				.collect(),
+		),
+		None => Value::Text(unquote(text)),
+	}
+}
+
+fn unquote(text: &str) -> String {
+	text.trim().trim_matches(['"', '\'']).into()
+}
@@ -0,0 +1,31 @@
+//! Exports documents as HTML, with math as MathML
+
+use pulldown_cmark::{Event, Parser, html};
+
+use super::math;
+
+/// Renders the document as a standalone HTML page with the provided title
+pub fn export(text: &str, title: &str) -> String {
+	let title = title
+		.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;");
+
+	format!(
+		"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+		body(text)
+	)
+}
+
+/// Renders the document as HTML, leaving out its front matter
+pub fn body(text: &str) -> String {
+	let events = Parser::new_ext(text, super::EXTENSIONS).map(|event| match event {
+		Event::InlineMath(latex) => Event::InlineHtml(math::to_mathml(&latex, false).into()),
+		Event::DisplayMath(latex) => Event::InlineHtml(math::to_mathml(&latex, true).into()),
+		event => event,
+	});
+
+	let mut html = String::new();
+	html::push_html(&mut html, events);
+	html
+}
@@ -0,0 +1,42 @@
+//! Finds the destinations of the links and images of a document, so that they can be checked
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Parser, Tag};
+
+/// A link or image, along with where it is written
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Target {
+	pub url: String,
+	pub image: bool,
+	/// Starting from 0
+	pub line: usize,
+	/// The byte range of the link within the line, cut off at the end of the line
+	pub columns: Range<usize>,
+}
+
+/// Every link and image of the text, including those using reference definitions
+pub fn targets(text: &str) -> Vec<Target> {
+	Parser::new_ext(text, super::EXTENSIONS)
+		.into_offset_iter()
+		.filter_map(|(event, range)| match event {
+			Event::Start(Tag::Link { dest_url, .. }) => Some((dest_url, false, range)),
+			Event::Start(Tag::Image { dest_url, .. }) => Some((dest_url, true, range)),
+			_ => None,
+		})
+		.map(|(url, image, range)| {
+			let start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+			let end = text[range.start..]
+				.find('\n')
+				.map_or(range.end, |i| range.start + i)
+				.min(range.end);
+
+			Target {
+				url: url.to_string(),
+				image,
+				line: text[..range.start].matches('\n').count(),
+				columns: range.start - start..end - start,
+			}
+		})
+		.collect()
+}
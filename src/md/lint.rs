@@ -0,0 +1,309 @@
+//! Checks for common problems in a document, similar to markdownlint
+
+use std::{collections::HashSet, ops::Range};
+
+use pulldown_cmark::{BrokenLink, Event, LinkType, Parser, Tag, TagEnd};
+
+use super::block::{self, Kind};
+
+/// A check that can be enabled or disabled
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Rule {
+	/// Headings only go down by one level at a time
+	HeadingIncrement,
+	/// Headings do not share their text
+	DuplicateHeading,
+	/// Reference links point to a definition
+	BrokenReference,
+	/// Images have alt text
+	MissingAlt,
+	/// Lines do not end with whitespace, other than hard breaks
+	TrailingSpaces,
+	/// Unordered lists all use the same marker, except lists right after another one, which are
+	/// only kept apart by using a different one
+	ListMarker,
+	/// URLs are placed in links
+	BareUrl,
+}
+
+/// A problem found in the document
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+	pub rule: Rule,
+	/// Starting from 0
+	pub line: usize,
+	/// The byte range of the problem within the line
+	pub columns: Range<usize>,
+	/// The text the problem is about, such as the duplicated heading
+	pub detail: String,
+}
+
+impl Rule {
+	pub const ALL: [Self; 7] = [
+		Self::HeadingIncrement,
+		Self::DuplicateHeading,
+		Self::BrokenReference,
+		Self::MissingAlt,
+		Self::TrailingSpaces,
+		Self::ListMarker,
+		Self::BareUrl,
+	];
+
+	/// The name used to refer to the rule in the config
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::HeadingIncrement => "heading_increment",
+			Self::DuplicateHeading => "duplicate_heading",
+			Self::BrokenReference => "broken_reference",
+			Self::MissingAlt => "missing_alt",
+			Self::TrailingSpaces => "trailing_spaces",
+			Self::ListMarker => "list_marker",
+			Self::BareUrl => "bare_url",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|rule| rule.name() == name)
+	}
+}
+
+/// Checks the text using the enabled rules, returning the problems sorted by their position
+pub fn lint(text: &str, rules: &HashSet<Rule>) -> Vec<Diagnostic> {
+	let mut linter = Linter {
+		text,
+		// Carriage returns of CRLF line endings are not part of the lines
+		lines: text
+			.split('\n')
+			.map(|line| line.strip_suffix('\r').unwrap_or(line))
+			.collect(),
+		rules,
+		diagnostics: vec![],
+	};
+
+	linter.events();
+	linter.bullets();
+	linter.lines();
+
+	let mut diagnostics = linter.diagnostics;
+	diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.columns.start));
+	diagnostics
+}
+
+struct Linter<'a> {
+	text: &'a str,
+	lines: Vec<&'a str>,
+	rules: &'a HashSet<Rule>,
+	diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+	/// Adds a problem spanning the byte range of the text, cut off at the end of its first line
+	fn push(&mut self, rule: Rule, range: Range<usize>, detail: impl Into<String>) {
+		if !self.rules.contains(&rule) {
+			return;
+		}
+
+		let line = self.text[..range.start].matches('\n').count();
+		let start = self.text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+		let len = self.lines[line].len();
+
+		self.diagnostics.push(Diagnostic {
+			rule,
+			line,
+			columns: (range.start - start).min(len)..(range.end - start).min(len),
+			detail: detail.into(),
+		});
+	}
+
+	/// Rules that need the document to be parsed
+	fn events(&mut self) {
+		let mut broken = vec![];
+		let events = {
+			let callback = |link: BrokenLink| {
+				// Shortcut links are too often just text in brackets
+				if matches!(link.link_type, LinkType::Reference | LinkType::Collapsed) {
+					broken.push((link.span, link.reference.to_string()));
+				}
+				None
+			};
+
+			Parser::new_with_broken_link_callback(self.text, super::EXTENSIONS, Some(callback))
+				.into_offset_iter()
+				.collect::<Vec<_>>()
+		};
+
+		for (span, reference) in broken {
+			self.push(Rule::BrokenReference, span, reference);
+		}
+
+		let mut headings = HashSet::new();
+		let mut level = None;
+		// The heading and image being read, along with their text
+		let mut heading: Option<(Range<usize>, String)> = None;
+		let mut image: Option<(Range<usize>, String)> = None;
+		// How many links, code blocks and metadata blocks the text is in
+		let mut links = 0;
+		let mut verbatim = 0;
+
+		for (event, range) in events {
+			match event {
+				Event::Start(Tag::Heading { level: new, .. }) => {
+					let new = new as usize;
+					if let Some(expected) =
+						level.map(|level| level + 1).filter(|level| new > *level)
+					{
+						self.push(Rule::HeadingIncrement, range.clone(), expected.to_string());
+					}
+					level = Some(new);
+					heading = Some((range, String::new()));
+				}
+				Event::End(TagEnd::Heading(_)) => {
+					if let Some((range, text)) = heading.take() {
+						let text = text.trim().to_string();
+						if !headings.insert(text.clone()) {
+							self.push(Rule::DuplicateHeading, range, text);
+						}
+					}
+				}
+
+				Event::Start(Tag::Image { .. }) => {
+					links += 1;
+					image = Some((range, String::new()));
+				}
+				Event::End(TagEnd::Image) => {
+					links -= 1;
+					if let Some((range, _)) = image.take().filter(|(_, alt)| alt.trim().is_empty())
+					{
+						self.push(Rule::MissingAlt, range, "");
+					}
+				}
+
+				Event::Start(Tag::Link { .. }) => links += 1,
+				Event::End(TagEnd::Link) => links -= 1,
+
+				Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => verbatim += 1,
+				Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => verbatim -= 1,
+
+				Event::Text(ref text) | Event::Code(ref text) => {
+					for (_, current) in [&mut heading, &mut image].into_iter().flatten() {
+						current.push_str(text);
+					}
+					if links == 0 && verbatim == 0 && matches!(event, Event::Text(_)) {
+						self.bare_urls(range);
+					}
+				}
+
+				_ => (),
+			}
+		}
+	}
+
+	fn bare_urls(&mut self, range: Range<usize>) {
+		let source = &self.text[range.clone()];
+
+		for (i, _) in source.match_indices("http") {
+			let url = &source[i..];
+			if !(url.starts_with("http://") || url.starts_with("https://")) {
+				continue;
+			}
+
+			let len = url
+				.find(|ch: char| ch.is_whitespace() || matches!(ch, '<' | '>' | '"'))
+				.unwrap_or(url.len());
+			// Punctuation after the URL usually belongs to the sentence
+			let url = url[..len].trim_end_matches(['.', ',', ':', ';', '!', '?', ')']);
+
+			let start = range.start + i;
+			self.push(Rule::BareUrl, start..start + url.len(), url);
+		}
+	}
+
+	/// Checks the bullets of unordered lists against the first one, which decides the style of
+	/// the document, like [super::fmt::format] places them
+	fn bullets(&mut self) {
+		let parser = Parser::new_ext(self.text, super::EXTENSIONS).into_offset_iter();
+		let mut style = None;
+
+		// The bullet of the list each open container ends with, if its last block is one
+		let mut containers: Vec<Option<char>> = vec![None];
+		// Whether each open list is unordered and follows another list, along with its bullet
+		let mut lists: Vec<(bool, bool, Option<char>)> = vec![];
+
+		for (event, range) in parser {
+			match event {
+				Event::Start(Tag::List(start)) => {
+					let follows = containers.last_mut().and_then(Option::take).is_some();
+					lists.push((start.is_none(), follows, None));
+				}
+
+				Event::End(TagEnd::List(_)) => {
+					let bullet = lists.pop().and_then(|(_, _, bullet)| bullet);
+					if let Some(last) = containers.last_mut() {
+						*last = bullet;
+					}
+				}
+
+				Event::Start(Tag::Item) => {
+					containers.push(None);
+
+					let Some((true, follows, bullet @ None)) = lists.last_mut() else {
+						continue;
+					};
+					let ch = self.text[range.start..].chars().next().unwrap_or_default();
+					if !matches!(ch, '-' | '*' | '+') {
+						continue;
+					}
+					*bullet = Some(ch);
+
+					let expected = *style.get_or_insert(ch);
+					if !*follows && ch != expected {
+						self.push(Rule::ListMarker, range.start..range.start + 1, expected);
+					}
+				}
+
+				Event::Start(Tag::BlockQuote(_) | Tag::FootnoteDefinition(_)) => {
+					if let Some(last) = containers.last_mut() {
+						*last = None;
+					}
+					containers.push(None);
+				}
+
+				Event::End(TagEnd::Item | TagEnd::BlockQuote(_) | TagEnd::FootnoteDefinition) => {
+					containers.pop();
+				}
+
+				// Any other block in between keeps the lists apart
+				Event::End(_) => (),
+				_ => {
+					if let Some(last) = containers.last_mut() {
+						*last = None;
+					}
+				}
+			}
+		}
+	}
+
+	/// Rules that only need the lines of the document
+	fn lines(&mut self) {
+		let kinds = block::classify(self.text);
+		let mut start = 0;
+
+		for (i, line) in self.lines.clone().into_iter().enumerate() {
+			let kind = kinds[i];
+			let range = |columns: Range<usize>| start + columns.start..start + columns.end;
+
+			// Two spaces are a hard break within a paragraph
+			let trimmed = line.trim_end().len();
+			let spaces = line.len() - trimmed;
+			let hard_break = kind == Kind::Prose
+				&& kinds.get(i + 1) == Some(&Kind::Prose)
+				&& line[trimmed..] == *"  ";
+			if spaces > 0 && !hard_break && !matches!(kind, Kind::Code | Kind::FrontMatter) {
+				self.push(Rule::TrailingSpaces, range(trimmed..line.len()), "");
+			}
+
+			let end = start + line.len();
+			start = end + usize::from(self.text[end..].starts_with('\r')) + 1;
+		}
+	}
+}
@@ -0,0 +1,83 @@
+use super::block;
+
+/// What pressing Enter on a line of a list or blockquote does
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Enter {
+	/// Breaks the line, starting the new one with the prefix
+	Continue(String),
+	/// Replaces everything before the cursor with the prefix, ending the list or blockquote
+	End(String),
+}
+
+/// Decides how to continue the line when Enter is pressed at the byte column.
+///
+/// Returns None if the line is not part of a list or blockquote, or if the cursor is before its
+/// text, in which case a plain line break should be inserted.
+pub fn enter(line: &str, column: usize) -> Option<Enter> {
+	let parts = block::split(line);
+	let prefix_len = line.len() - parts.text.len();
+
+	if (parts.list.is_none() && parts.quote.is_empty()) || column < prefix_len {
+		return None;
+	}
+
+	if parts.text.trim().is_empty() {
+		// An empty item ends the list, and an empty quote ends the innermost blockquote
+		let prefix = match parts.list {
+			Some(_) => parts.quote,
+			None => match parts.quote.trim_end().rfind('>') {
+				Some(last) => &parts.quote[..last],
+				None => "",
+			},
+		};
+
+		return Some(Enter::End(prefix.into()));
+	}
+
+	let marker = match parts.list {
+		Some(list) => list.next().marker(),
+		None => String::new(),
+	};
+
+	let prefix = format!("{}{}{marker}", parts.quote, parts.indent);
+	Some(Enter::Continue(prefix))
+}
+
+/// Checks or unchecks the task list item whose checkbox starts at the byte offset, returning the
+/// new text.
+///
+/// Returns None if there is no checkbox at the offset, such as when the text changed since it was
+/// found.
+pub fn toggle_task(text: &str, offset: usize) -> Option<String> {
+	let checkbox = match text.get(offset..offset + 3)? {
+		"[ ]" => "[x]",
+		"[x]" | "[X]" => "[ ]",
+		_ => return None,
+	};
+
+	let mut text = text.to_string();
+	text.replace_range(offset..offset + 3, checkbox);
+	Some(text)
+}
+
+/// Checks if the line is a list item that can be indented
+pub fn is_item(line: &str) -> bool {
+	block::split(line).list.is_some()
+}
+
+/// The byte offset indentation is inserted at, after any blockquote markers
+pub fn indent_start(line: &str) -> usize {
+	block::split(line).quote.len()
+}
+
+/// How many bytes of indentation to remove when outdenting the line
+pub fn outdent_len(line: &str, tab_len: usize) -> usize {
+	let indent = block::split(line).indent;
+
+	if indent.starts_with('\t') {
+		1
+	} else {
+		let spaces = indent.len() - indent.trim_start_matches(' ').len();
+		spaces.min(tab_len.max(1))
+	}
+}
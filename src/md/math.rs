@@ -0,0 +1,625 @@
+//! Converts the subset of LaTeX used in math spans and blocks into Unicode text and MathML
+
+use std::{iter::Peekable, str::Chars};
+
+/// Parsed LaTeX
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+	/// A letter, digit or operator typed as is
+	Char(char),
+	/// A command that stands for a symbol, such as `\alpha`
+	Symbol(Symbol),
+	/// A command for a function name, such as `\sin`
+	Function(&'static str),
+	Text(String),
+	Group(Vec<Node>),
+	Frac(Box<Node>, Box<Node>),
+	Sqrt(Option<Box<Node>>, Box<Node>),
+	Scripts {
+		base: Box<Node>,
+		sub: Option<Box<Node>>,
+		sup: Option<Box<Node>>,
+	},
+	Space,
+	/// A command that is not supported, which is shown as written
+	Unknown(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Symbol {
+	ch: char,
+	/// Whether it is an operator, rather than an identifier
+	operator: bool,
+}
+
+const GREEK: [(&str, char); 40] = [
+	("alpha", 'α'),
+	("beta", 'β'),
+	("gamma", 'γ'),
+	("delta", 'δ'),
+	("epsilon", 'ϵ'),
+	("varepsilon", 'ε'),
+	("zeta", 'ζ'),
+	("eta", 'η'),
+	("theta", 'θ'),
+	("vartheta", 'ϑ'),
+	("iota", 'ι'),
+	("kappa", 'κ'),
+	("lambda", 'λ'),
+	("mu", 'μ'),
+	("nu", 'ν'),
+	("xi", 'ξ'),
+	("pi", 'π'),
+	("rho", 'ρ'),
+	("sigma", 'σ'),
+	("tau", 'τ'),
+	("upsilon", 'υ'),
+	("phi", 'ϕ'),
+	("varphi", 'φ'),
+	("chi", 'χ'),
+	("psi", 'ψ'),
+	("omega", 'ω'),
+	("Gamma", 'Γ'),
+	("Delta", 'Δ'),
+	("Theta", 'Θ'),
+	("Lambda", 'Λ'),
+	("Xi", 'Ξ'),
+	("Pi", 'Π'),
+	("Sigma", 'Σ'),
+	("Upsilon", 'Υ'),
+	("Phi", 'Φ'),
+	("Psi", 'Ψ'),
+	("Omega", 'Ω'),
+	("infty", '∞'),
+	("partial", '∂'),
+	("nabla", '∇'),
+];
+
+const OPERATORS: [(&str, char); 50] = [
+	("times", '×'),
+	("cdot", '·'),
+	("div", '÷'),
+	("pm", '±'),
+	("mp", '∓'),
+	("leq", '≤'),
+	("le", '≤'),
+	("geq", '≥'),
+	("ge", '≥'),
+	("neq", '≠'),
+	("ne", '≠'),
+	("approx", '≈'),
+	("equiv", '≡'),
+	("sim", '∼'),
+	("propto", '∝'),
+	("sum", '∑'),
+	("prod", '∏'),
+	("int", '∫'),
+	("oint", '∮'),
+	("to", '→'),
+	("rightarrow", '→'),
+	("leftarrow", '←'),
+	("Rightarrow", '⇒'),
+	("Leftarrow", '⇐'),
+	("leftrightarrow", '↔'),
+	("Leftrightarrow", '⇔'),
+	("implies", '⟹'),
+	("iff", '⟺'),
+	("mapsto", '↦'),
+	("in", '∈'),
+	("notin", '∉'),
+	("subset", '⊂'),
+	("subseteq", '⊆'),
+	("supset", '⊃'),
+	("cup", '∪'),
+	("cap", '∩'),
+	("forall", '∀'),
+	("exists", '∃'),
+	("neg", '¬'),
+	("wedge", '∧'),
+	("vee", '∨'),
+	("circ", '∘'),
+	("ldots", '…'),
+	("cdots", '⋯'),
+	("mid", '|'),
+	("langle", '⟨'),
+	("rangle", '⟩'),
+	("lfloor", '⌊'),
+	("rfloor", '⌋'),
+	("emptyset", '∅'),
+];
+
+const FUNCTIONS: [&str; 16] = [
+	"sin", "cos", "tan", "cot", "sec", "csc", "log", "ln", "exp", "lim", "max", "min", "sup",
+	"inf", "det", "gcd",
+];
+
+const SUPERSCRIPTS: [(char, char); 16] = [
+	('0', '⁰'),
+	('1', '¹'),
+	('2', '²'),
+	('3', '³'),
+	('4', '⁴'),
+	('5', '⁵'),
+	('6', '⁶'),
+	('7', '⁷'),
+	('8', '⁸'),
+	('9', '⁹'),
+	('+', '⁺'),
+	('-', '⁻'),
+	('=', '⁼'),
+	('(', '⁽'),
+	(')', '⁾'),
+	('n', 'ⁿ'),
+];
+
+const SUBSCRIPTS: [(char, char); 25] = [
+	('0', '₀'),
+	('1', '₁'),
+	('2', '₂'),
+	('3', '₃'),
+	('4', '₄'),
+	('5', '₅'),
+	('6', '₆'),
+	('7', '₇'),
+	('8', '₈'),
+	('9', '₉'),
+	('+', '₊'),
+	('-', '₋'),
+	('=', '₌'),
+	('(', '₍'),
+	(')', '₎'),
+	('a', 'ₐ'),
+	('e', 'ₑ'),
+	('i', 'ᵢ'),
+	('j', 'ⱼ'),
+	('k', 'ₖ'),
+	('m', 'ₘ'),
+	('n', 'ₙ'),
+	('o', 'ₒ'),
+	('x', 'ₓ'),
+	('t', 'ₜ'),
+];
+
+/// Renders LaTeX as plain text, using Unicode symbols, superscripts and subscripts
+pub fn to_unicode(latex: &str) -> String {
+	let mut text = String::new();
+	for node in parse(latex) {
+		node.unicode(&mut text);
+	}
+	text.trim().into()
+}
+
+/// Renders LaTeX as a MathML element, which is displayed as a block if `display` is set
+pub fn to_mathml(latex: &str, display: bool) -> String {
+	let mut mathml = String::new();
+	mathml_row(&parse(latex), &mut mathml);
+
+	let display = if display { r#" display="block""# } else { "" };
+	format!("<math{display}><mrow>{mathml}</mrow></math>")
+}
+
+fn parse(latex: &str) -> Vec<Node> {
+	let mut chars = latex.chars().peekable();
+	let mut nodes = vec![];
+	while chars.peek().is_some() {
+		if let Some(node) = node(&mut chars) {
+			push(&mut nodes, node, &mut chars);
+		}
+	}
+	nodes
+}
+
+/// Adds a node, attaching the scripts that follow it
+fn push(nodes: &mut Vec<Node>, node: Node, chars: &mut Peekable<Chars>) {
+	let mut sub = None;
+	let mut sup = None;
+
+	while let Some(script) = chars.next_if(|ch| matches!(ch, '^' | '_')) {
+		let argument = argument(chars).map(Box::new);
+		if script == '^' {
+			sup = argument;
+		} else {
+			sub = argument;
+		}
+	}
+
+	if sub.is_none() && sup.is_none() {
+		nodes.push(node);
+	} else {
+		nodes.push(Node::Scripts {
+			base: Box::new(node),
+			sub,
+			sup,
+		});
+	}
+}
+
+/// Parses the next node, returning None at the end of a group or the text
+fn node(chars: &mut Peekable<Chars>) -> Option<Node> {
+	let ch = chars.next()?;
+
+	Some(match ch {
+		'{' => Node::Group(group(chars)),
+		'}' => return None,
+		'\\' => command(chars),
+		ch if ch.is_whitespace() => return None,
+		ch => Node::Char(ch),
+	})
+}
+
+/// Parses the nodes up to the closing brace
+fn group(chars: &mut Peekable<Chars>) -> Vec<Node> {
+	let mut nodes = vec![];
+	loop {
+		match chars.peek() {
+			None => break,
+			Some('}') => {
+				chars.next();
+				break;
+			}
+			_ => {
+				if let Some(node) = node(chars) {
+					push(&mut nodes, node, chars);
+				}
+			}
+		}
+	}
+	nodes
+}
+
+/// The argument of a command or script, which is either a group or a single token
+fn argument(chars: &mut Peekable<Chars>) -> Option<Node> {
+	while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+
+	match chars.peek()? {
+		'{' => {
+			chars.next();
+			Some(Node::Group(group(chars)))
+		}
+		'\\' => {
+			chars.next();
+			Some(command(chars))
+		}
+		_ => chars.next().map(Node::Char),
+	}
+}
+
+/// The raw text of a group argument, used by commands like `\text`
+fn raw_argument(chars: &mut Peekable<Chars>) -> String {
+	while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+	if chars.next_if_eq(&'{').is_none() {
+		return String::new();
+	}
+
+	let mut depth = 0;
+	let mut text = String::new();
+	for ch in chars.by_ref() {
+		match ch {
+			'{' => depth += 1,
+			'}' if depth == 0 => break,
+			'}' => depth -= 1,
+			_ => (),
+		}
+		text.push(ch);
+	}
+	text
+}
+
+fn command(chars: &mut Peekable<Chars>) -> Node {
+	let mut name = String::new();
+	while let Some(ch) = chars.next_if(|ch| ch.is_ascii_alphabetic()) {
+		name.push(ch);
+	}
+
+	// Commands made of a single symbol, such as `\{` or `\,`
+	if name.is_empty() {
+		return match chars.next() {
+			Some(',' | ';' | ':' | ' ') => Node::Space,
+			Some('!') => Node::Group(vec![]),
+			Some('\\') => Node::Char('\n'),
+			Some(ch) => Node::Char(ch),
+			None => Node::Char('\\'),
+		};
+	}
+
+	let symbol = |ch, operator| Node::Symbol(Symbol { ch, operator });
+
+	if let Some((_, ch)) = GREEK.iter().find(|(command, _)| *command == name) {
+		return symbol(*ch, false);
+	}
+	if let Some((_, ch)) = OPERATORS.iter().find(|(command, _)| *command == name) {
+		return symbol(*ch, true);
+	}
+	if let Some(function) = FUNCTIONS.iter().find(|function| **function == name) {
+		return Node::Function(function);
+	}
+
+	match name.as_str() {
+		"frac" | "dfrac" | "tfrac" => {
+			let numerator = argument(chars).unwrap_or(Node::Group(vec![]));
+			let denominator = argument(chars).unwrap_or(Node::Group(vec![]));
+			Node::Frac(Box::new(numerator), Box::new(denominator))
+		}
+		"sqrt" => {
+			let index = chars.next_if_eq(&'[').map(|_| {
+				let mut index = vec![];
+				while let Some(ch) = chars.next_if(|ch| *ch != ']') {
+					index.push(Node::Char(ch));
+				}
+				chars.next();
+				Box::new(Node::Group(index))
+			});
+			let radicand = argument(chars).unwrap_or(Node::Group(vec![]));
+			Node::Sqrt(index, Box::new(radicand))
+		}
+		"text" | "textrm" | "mathrm" | "operatorname" => Node::Text(raw_argument(chars)),
+		// Styles are not supported, so only their content is kept
+		"mathbf" | "mathit" | "boldsymbol" | "mathbb" | "mathcal" => {
+			argument(chars).unwrap_or(Node::Group(vec![]))
+		}
+		// Delimiters are shown without being resized
+		"left" | "right" | "big" | "Big" | "bigg" | "Bigg" => Node::Group(vec![]),
+		"quad" | "qquad" => Node::Space,
+		_ => Node::Unknown(format!("\\{name}")),
+	}
+}
+
+impl Node {
+	fn unicode(&self, text: &mut String) {
+		match self {
+			Self::Char('\n') => text.push('\n'),
+			Self::Char(ch) if is_operator(*ch) => {
+				text.push(' ');
+				text.push(*ch);
+				text.push(' ');
+			}
+			Self::Char(ch) => text.push(*ch),
+			Self::Symbol(Symbol { ch, operator: true }) if is_relation(*ch) => {
+				text.push(' ');
+				text.push(*ch);
+				text.push(' ');
+			}
+			Self::Symbol(Symbol { ch, .. }) => text.push(*ch),
+			Self::Function(name) => {
+				text.push_str(name);
+				text.push(' ');
+			}
+			Self::Text(content) => text.push_str(content),
+			Self::Group(nodes) => {
+				for node in nodes {
+					node.unicode(text);
+				}
+			}
+			Self::Frac(numerator, denominator) => {
+				text.push_str(&numerator.unicode_operand());
+				text.push('/');
+				text.push_str(&denominator.unicode_operand());
+			}
+			Self::Sqrt(index, radicand) => {
+				let index = index.as_ref().map(|index| index.unicode_string());
+				match index.as_deref() {
+					None | Some("2") => text.push('√'),
+					Some("3") => text.push('∛'),
+					Some("4") => text.push('∜'),
+					Some(index) => {
+						text.push_str(&script(index, &SUPERSCRIPTS, '^'));
+						text.push('√');
+					}
+				}
+				text.push_str(&radicand.unicode_operand());
+			}
+			Self::Scripts { base, sub, sup } => {
+				base.unicode(text);
+				if let Some(sub) = sub {
+					text.push_str(&script(&sub.unicode_string(), &SUBSCRIPTS, '_'));
+				}
+				if let Some(sup) = sup {
+					text.push_str(&script(&sup.unicode_string(), &SUPERSCRIPTS, '^'));
+				}
+				// Separates the limits of sums and integrals from what follows them
+				if let Self::Symbol(Symbol { operator: true, .. }) = **base {
+					text.push(' ');
+				}
+			}
+			Self::Space => text.push(' '),
+			Self::Unknown(command) => text.push_str(command),
+		}
+	}
+
+	fn unicode_string(&self) -> String {
+		let mut text = String::new();
+		self.unicode(&mut text);
+		text.trim().into()
+	}
+
+	/// The text of an operand, placed in parentheses if it is made of multiple parts
+	fn unicode_operand(&self) -> String {
+		let text = self.unicode_string();
+		if text.chars().count() > 1 && text.chars().any(|ch| !ch.is_alphanumeric()) {
+			format!("({text})")
+		} else {
+			text
+		}
+	}
+
+	fn mathml(&self, mathml: &mut String) {
+		match self {
+			Self::Char('\n') => mathml.push_str("<mspace linebreak=\"newline\"/>"),
+			Self::Char(ch) if ch.is_numeric() || *ch == '.' => {
+				element("mn", &ch.to_string(), mathml)
+			}
+			Self::Char(ch) if ch.is_alphabetic() => element("mi", &ch.to_string(), mathml),
+			Self::Char(ch) => element("mo", &ch.to_string(), mathml),
+			Self::Symbol(Symbol { ch, operator }) => {
+				element(if *operator { "mo" } else { "mi" }, &ch.to_string(), mathml)
+			}
+			Self::Function(name) => element("mi", name, mathml),
+			Self::Text(text) => element("mtext", text, mathml),
+			Self::Group(nodes) => {
+				mathml.push_str("<mrow>");
+				mathml_row(nodes, mathml);
+				mathml.push_str("</mrow>");
+			}
+			Self::Frac(numerator, denominator) => {
+				mathml.push_str("<mfrac>");
+				numerator.mathml(mathml);
+				denominator.mathml(mathml);
+				mathml.push_str("</mfrac>");
+			}
+			Self::Sqrt(None, radicand) => {
+				mathml.push_str("<msqrt>");
+				radicand.mathml(mathml);
+				mathml.push_str("</msqrt>");
+			}
+			Self::Sqrt(Some(index), radicand) => {
+				mathml.push_str("<mroot>");
+				radicand.mathml(mathml);
+				index.mathml(mathml);
+				mathml.push_str("</mroot>");
+			}
+			Self::Scripts { base, sub, sup } => {
+				let tag = match (sub, sup) {
+					(Some(_), Some(_)) => "msubsup",
+					(Some(_), None) => "msub",
+					_ => "msup",
+				};
+
+				mathml.push_str(&format!("<{tag}>"));
+				base.mathml(mathml);
+				for script in [sub, sup].into_iter().flatten() {
+					script.mathml(mathml);
+				}
+				mathml.push_str(&format!("</{tag}>"));
+			}
+			Self::Space => mathml.push_str("<mspace width=\"0.5em\"/>"),
+			Self::Unknown(command) => element("merror", command, mathml),
+		}
+	}
+}
+
+/// Renders nodes one after another, joining digits into numbers
+fn mathml_row(nodes: &[Node], mathml: &mut String) {
+	let mut number = String::new();
+
+	for node in nodes {
+		match node {
+			Node::Char(ch) if ch.is_numeric() || (*ch == '.' && !number.is_empty()) => {
+				number.push(*ch);
+				continue;
+			}
+			_ if !number.is_empty() => element("mn", &std::mem::take(&mut number), mathml),
+			_ => (),
+		}
+		node.mathml(mathml);
+	}
+
+	if !number.is_empty() {
+		element("mn", &number, mathml);
+	}
+}
+
+fn element(tag: &str, content: &str, mathml: &mut String) {
+	let content = content
+		.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;");
+	mathml.push_str(&format!("<{tag}>{content}</{tag}>"));
+}
+
+/// Converts text into superscript or subscript characters, falling back to the LaTeX notation
+fn script(text: &str, table: &[(char, char)], fallback: char) -> String {
+	let text = text.replace(' ', "");
+	let converted = text
+		.chars()
+		.map(|ch| {
+			table
+				.iter()
+				.find(|(from, _)| *from == ch)
+				.map(|(_, to)| *to)
+		})
+		.collect::<Option<String>>();
+
+	match converted {
+		Some(converted) => converted,
+		None if text.chars().count() == 1 => format!("{fallback}{text}"),
+		None => format!("{fallback}({text})"),
+	}
+}
+
+fn is_operator(ch: char) -> bool {
+	matches!(ch, '+' | '-' | '=' | '<' | '>')
+}
+
+fn is_relation(ch: char) -> bool {
+	!matches!(
+		ch,
+		'∑' | '∏' | '∫' | '∮' | '¬' | '…' | '⋯' | '|' | '⟨' | '⟩' | '⌊' | '⌋' | '∅'
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn renders_unicode() {
+		assert_eq!(to_unicode(r"\alpha + x^2"), "α + x²");
+		assert_eq!(to_unicode(r"x_{i}^{2}"), "xᵢ²");
+		assert_eq!(to_unicode(r"\frac{a+b}{c}"), "(a + b)/c");
+		assert_eq!(to_unicode(r"\sqrt[3]{x}"), "∛x");
+		assert_eq!(to_unicode(r"\sin x \leq 1"), "sin x ≤ 1");
+		assert_eq!(to_unicode(r"\text{if } x"), "if x");
+	}
+
+	#[test]
+	fn falls_back_without_script_characters() {
+		assert_eq!(to_unicode("x^{q}"), "x^q");
+		assert_eq!(to_unicode("x^{ab}"), "x^(ab)");
+	}
+
+	#[test]
+	fn renders_mathml() {
+		assert_eq!(
+			to_mathml("x^2", false),
+			"<math><mrow><msup><mi>x</mi><mn>2</mn></msup></mrow></math>"
+		);
+		assert!(to_mathml("x", true).starts_with(r#"<math display="block">"#));
+		assert!(to_mathml(r"\unknown", false).contains("<merror>"));
+	}
+
+	#[test]
+	fn handles_malformed_input() {
+		let latex = [
+			"",
+			"{",
+			"}",
+			"}{",
+			"{{{",
+			"^",
+			"_",
+			"x^",
+			"x_{",
+			"^^",
+			"x^2^3",
+			r"\",
+			r"\\",
+			r"\frac",
+			r"\frac{",
+			r"\frac{a}",
+			r"\sqrt[",
+			r"\sqrt[3",
+			r"\sqrt[]{}",
+			r"\text{",
+			r"\left(",
+			r"\ñ",
+			"é^ü_ß",
+			"\\alpha\u{0}",
+			"& % $ #",
+		];
+		for latex in latex {
+			to_unicode(latex);
+			let mathml = to_mathml(latex, false);
+			assert!(mathml.starts_with("<math>") && mathml.ends_with("</math>"));
+		}
+	}
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+/// What typing a character next to the cursor does
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Typed {
+	/// Inserts both characters, placing the cursor between them
+	Pair(char, char),
+	/// Moves the cursor over the closing character that is already there
+	Skip,
+}
+
+/// Decides what typing the character does, given the text of the line on each side of the cursor.
+///
+/// Returns None if it should just be inserted.
+pub fn typed(ch: char, before: &str, after: &str, pairs: &HashMap<char, char>) -> Option<Typed> {
+	let mut previous = before.chars().rev();
+	let (first, second) = (previous.next(), previous.next());
+	let next = after.chars().next();
+
+	let symmetric = pairs.get(&ch) == Some(&ch);
+
+	if next == Some(ch) && pairs.values().any(|close| *close == ch) {
+		// Typing into an empty symmetric pair nests another one, which is how "**" is typed
+		if symmetric && first == Some(ch) && second.is_none_or(char::is_whitespace) {
+			return Some(Typed::Pair(ch, ch));
+		}
+		return Some(Typed::Skip);
+	}
+
+	let close = *pairs.get(&ch)?;
+
+	// At the start of a line, it is more likely to be the marker of a list item, such as "* "
+	if matches!(ch, '*' | '-' | '+')
+		&& before
+			.chars()
+			.all(|before| before == ch || before == '>' || before.is_whitespace())
+	{
+		return None;
+	}
+
+	// Pairing up in the middle of a word is rarely wanted, such as with "snake_case" or "don't"
+	if next.is_some_and(char::is_alphanumeric)
+		|| (symmetric && first.is_some_and(char::is_alphanumeric))
+	{
+		return None;
+	}
+
+	Some(Typed::Pair(ch, close))
+}
+
+/// Checks if the cursor is between an opening character and the one closing it
+pub fn is_empty_pair(before: &str, after: &str, pairs: &HashMap<char, char>) -> bool {
+	match (before.chars().next_back(), after.chars().next()) {
+		(Some(open), Some(close)) => pairs.get(&open) == Some(&close),
+		_ => false,
+	}
+}
+
+/// The text closing a delimiter, such as ")]" for "[(" or "</kbd>" for "<kbd>"
+pub fn closing(open: &str) -> String {
+	if let Some(tag) = open.strip_prefix('<').and_then(|tag| tag.strip_suffix('>')) {
+		let name = tag.split_whitespace().next().unwrap_or_default();
+		return format!("</{name}>");
+	}
+
+	open.chars()
+		.rev()
+		.map(|ch| match ch {
+			'(' => ')',
+			'[' => ']',
+			'{' => '}',
+			'<' => '>',
+			'“' => '”',
+			'‘' => '’',
+			ch => ch,
+		})
+		.collect()
+}
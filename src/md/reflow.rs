@@ -0,0 +1,196 @@
+use std::ops::{Range, RangeInclusive};
+
+use super::block;
+
+/// Rewraps every paragraph touching the given lines so that they fit within the width,
+/// leaving everything other than prose untouched.
+///
+/// Returns the new text, along with the lines now taken up by the reflowed paragraphs.
+/// A width of 0 joins every paragraph into a single line.
+pub fn reflow(
+	text: &str,
+	lines: RangeInclusive<usize>,
+	width: usize,
+) -> Option<(String, Range<usize>)> {
+	let source = text.split('\n').collect::<Vec<&str>>();
+	let kinds = block::classify(text);
+
+	let mut out: Vec<String> = vec![];
+	let mut changed: Option<Range<usize>> = None;
+	let mut next = 0;
+
+	for paragraph in block::paragraphs(&source, &kinds) {
+		if paragraph.end <= *lines.start() || paragraph.start > *lines.end() {
+			continue;
+		}
+
+		out.extend(
+			source[next..paragraph.start]
+				.iter()
+				.map(|line| line.to_string()),
+		);
+		let start = out.len();
+		out.extend(wrap(&source[paragraph.clone()], width));
+
+		changed = Some(match changed {
+			Some(changed) => changed.start..out.len(),
+			None => start..out.len(),
+		});
+		next = paragraph.end;
+	}
+
+	out.extend(source[next..].iter().map(|line| line.to_string()));
+	changed.map(|changed| (out.join("\n"), changed))
+}
+
+/// Rewraps every paragraph in the text
+pub fn reflow_all(text: &str, width: usize) -> String {
+	match reflow(text, 0..=usize::MAX, width) {
+		Some((text, _)) => text,
+		None => text.into(),
+	}
+}
+
+/// Wraps the lines of a single paragraph
+fn wrap(lines: &[&str], width: usize) -> Vec<String> {
+	let width = if width == 0 { usize::MAX } else { width };
+
+	let first = block::split(lines[0]);
+	let continuation = first.continuation();
+
+	let words = std::iter::once(first.text)
+		.chain(lines[1..].iter().map(|line| block::strip(line)))
+		.flat_map(str::split_whitespace);
+
+	let mut out = vec![first.prefix()];
+	// Whether the current line has any words on it yet
+	let mut empty = true;
+
+	for word in words {
+		let line = out.last_mut().expect("There is always at least one line");
+		let len = line.chars().count() + 1 + word.chars().count();
+
+		// Words that would start a new block are kept on the line before
+		if !empty && len > width && !block::starts_block(word) {
+			out.push(format!("{continuation}{word}"));
+		} else {
+			if !empty {
+				line.push(' ');
+			}
+			line.push_str(word);
+		}
+
+		empty = false;
+	}
+
+	// Trailing spaces are the only part of a hard break that is not kept by the words
+	if lines[lines.len() - 1].ends_with("  ") {
+		out.last_mut()
+			.expect("There is always at least one line")
+			.push_str("  ");
+	}
+
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn wraps_touched_paragraphs() {
+		assert_eq!(
+			reflow("one two three four five\nsix\n\n- a b c d e f", 0..=0, 10),
+			Some((
+				"one two\nthree four\nfive six\n\n- a b c d e f".into(),
+				0..3
+			))
+		);
+		assert_eq!(
+			reflow("one two three\n\n- a b c d e f g h", 2..=2, 6),
+			Some(("one two three\n\n- a b\n  c d\n  e f\n  g h".into(), 2..6))
+		);
+	}
+
+	#[test]
+	fn keeps_prefixes() {
+		assert_eq!(
+			reflow("> quoted text that is long\n> more", 0..=1, 12),
+			Some(("> quoted\n> text that\n> is long\n> more".into(), 0..4))
+		);
+	}
+
+	#[test]
+	fn leaves_other_blocks() {
+		assert_eq!(
+			reflow(
+				"# Heading that is long\n\n```\ncode code code\n```",
+				0..=4,
+				5
+			),
+			None
+		);
+	}
+
+	#[test]
+	fn joins_without_width() {
+		assert_eq!(reflow_all("a\nb\n\nc\nd", 0), "a b\n\nc d");
+	}
+
+	#[test]
+	fn keeps_long_words() {
+		assert_eq!(
+			reflow("averyveryverylongword x", 0..=0, 5),
+			Some(("averyveryverylongword\nx".into(), 0..2))
+		);
+	}
+
+	#[test]
+	fn is_idempotent() {
+		let texts = [
+			"one two three four five\nsix\n\n- a b c d e f",
+			"> quoted text that is long\n> more",
+			"1. numbered item with text\n   continued here",
+		];
+		for text in texts {
+			let reflowed = reflow_all(text, 8);
+			assert_eq!(reflow_all(&reflowed, 8), reflowed, "{text:?}");
+		}
+	}
+
+	#[test]
+	fn handles_malformed_input() {
+		let texts = [
+			"",
+			"\n",
+			" ",
+			"-",
+			"- ",
+			">",
+			"> ",
+			"1.",
+			"#",
+			"\t\tindented",
+			"a\r\nb\r\n",
+			"é ü ß 漢字 漢字",
+			"- [ ]",
+			"> - > a",
+			"```\nopen",
+			"|a|\n|-|",
+		];
+		let ranges = [
+			0..=0,
+			0..=usize::MAX,
+			RangeInclusive::new(3, 1),
+			usize::MAX..=usize::MAX,
+		];
+
+		for text in texts {
+			for lines in ranges.clone() {
+				for width in [0, 1, 3, 80] {
+					reflow(text, lines.clone(), width);
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,115 @@
+//! Finds the words of a document that should be spell checked
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, LinkType, Parser, Tag, TagEnd};
+
+use super::front_matter;
+
+/// A word that is not in the dictionary
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Misspelling {
+	/// Starting from 0
+	pub line: usize,
+	/// The byte range of the word within the line
+	pub columns: Range<usize>,
+	pub word: String,
+}
+
+/// Checks every word of the prose in the text, skipping code, URLs and front matter
+pub fn check(text: &str, is_correct: impl Fn(&str) -> bool) -> Vec<Misspelling> {
+	words(text)
+		.into_iter()
+		.filter(|range| !is_correct(&text[range.clone()]))
+		.map(|range| {
+			let start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+			Misspelling {
+				line: text[..range.start].matches('\n').count(),
+				columns: range.start - start..range.end - start,
+				word: text[range].into(),
+			}
+		})
+		.collect()
+}
+
+/// The byte ranges of the words in the prose of the text
+pub fn words(text: &str) -> Vec<Range<usize>> {
+	let mut words = vec![];
+	// How many code blocks, metadata blocks and autolinks the text is in
+	let mut verbatim = 0;
+
+	for (event, range) in Parser::new_ext(text, super::EXTENSIONS).into_offset_iter() {
+		match event {
+			Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => verbatim += 1,
+			Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => verbatim -= 1,
+
+			Event::Start(Tag::Link {
+				link_type: LinkType::Autolink | LinkType::Email,
+				..
+			}) => verbatim += 1,
+			Event::End(TagEnd::Link) if verbatim > 0 => verbatim -= 1,
+
+			Event::Text(_) if verbatim == 0 => {
+				words.extend(
+					split(&text[range.clone()])
+						.into_iter()
+						.map(|word| range.start + word.start..range.start + word.end),
+				);
+			}
+
+			_ => (),
+		}
+	}
+
+	words
+}
+
+/// Splits text into words, leaving out URLs, numbers and acronyms
+fn split(text: &str) -> Vec<Range<usize>> {
+	let mut words = vec![];
+	let mut start = 0;
+
+	for token in text.split_whitespace() {
+		let offset = start + text[start..].find(token).unwrap_or_default();
+		start = offset + token.len();
+
+		if token.contains("://") || token.starts_with("www.") || token.contains('@') {
+			continue;
+		}
+
+		let mut word: Option<Range<usize>> = None;
+		for (i, ch) in token.char_indices().chain([(token.len(), ' ')]) {
+			let is_apostrophe = matches!(ch, '\'' | '’');
+
+			if ch.is_alphanumeric() || (is_apostrophe && word.is_some()) {
+				let range = word.get_or_insert(i..i);
+				range.end = i + ch.len_utf8();
+				continue;
+			}
+
+			let Some(range) = word.take() else {
+				continue;
+			};
+			// Apostrophes after a word are quotes, rather than part of it
+			let word = token[range.clone()].trim_end_matches(['\'', '’']);
+			let is_number = word.chars().any(|ch| ch.is_numeric());
+			let is_acronym = word.chars().all(|ch| ch.is_uppercase());
+
+			if !word.is_empty() && !is_number && !is_acronym {
+				words.push(offset + range.start..offset + range.start + word.len());
+			}
+		}
+	}
+
+	words
+}
+
+/// The language set by the "lang" or "language" field of the front matter, if there is any
+pub fn language(text: &str) -> Option<String> {
+	let front_matter = front_matter::parse(text);
+
+	["lang", "language"]
+		.into_iter()
+		.find_map(|key| front_matter.text(key))
+		.map(Into::into)
+}
@@ -0,0 +1,73 @@
+//! Counts the text of a document, leaving out its Markdown syntax
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Stats {
+	pub words: usize,
+	/// Characters other than whitespace
+	pub characters: usize,
+	pub lines: usize,
+}
+
+impl Stats {
+	/// How many minutes it takes to read the words, rounded up
+	pub fn reading_time(&self, words_per_minute: usize) -> usize {
+		self.words.div_ceil(words_per_minute.max(1))
+	}
+}
+
+/// Counts the rendered text, skipping front matter
+pub fn count(text: &str) -> Stats {
+	let mut stats = Stats {
+		lines: text.lines().count(),
+		..Stats::default()
+	};
+	let mut metadata = false;
+	// Words can be split between events, such as "**bold**text"
+	let mut in_word = false;
+
+	for event in Parser::new_ext(text, super::EXTENSIONS) {
+		let text = match event {
+			Event::Start(Tag::MetadataBlock(_)) => {
+				metadata = true;
+				continue;
+			}
+			Event::End(TagEnd::MetadataBlock(_)) => {
+				metadata = false;
+				continue;
+			}
+			_ if metadata => continue,
+
+			Event::Text(text) | Event::Code(text) => text,
+			Event::End(
+				TagEnd::Emphasis
+				| TagEnd::Strong
+				| TagEnd::Strikethrough
+				| TagEnd::Link
+				| TagEnd::Image,
+			) => continue,
+			// Every other tag ends a block
+			Event::SoftBreak | Event::HardBreak | Event::End(_) => {
+				in_word = false;
+				continue;
+			}
+			_ => continue,
+		};
+
+		for ch in text.chars() {
+			if ch.is_whitespace() {
+				in_word = false;
+				continue;
+			}
+
+			stats.characters += 1;
+			if !in_word {
+				stats.words += 1;
+				in_word = true;
+			}
+		}
+	}
+
+	stats
+}
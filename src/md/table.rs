@@ -0,0 +1,528 @@
+use std::{cmp::Ordering, ops::Range};
+
+use super::{
+	block::{self, Kind},
+	format::Change,
+};
+
+/// Commands that edit the table the cursor is in
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Command {
+	/// Lines up the columns
+	Align,
+	/// Moves to the next cell, adding a row after the last one
+	NextCell,
+	PreviousCell,
+	/// Moves to the same column of the next row, adding a row after the last one
+	NextRow,
+	InsertRow,
+	DeleteRow,
+	InsertColumn,
+	DeleteColumn,
+	/// Sorts the rows by the column the cursor is in, reversing the order if already sorted
+	Sort,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Align {
+	None,
+	Left,
+	Center,
+	Right,
+}
+
+/// A pipe table, with the header being the first row
+#[derive(Debug, Clone)]
+struct Table {
+	/// Blockquote markers and indentation placed before every line
+	prefix: String,
+	aligns: Vec<Align>,
+	rows: Vec<Vec<String>>,
+}
+
+/// Runs the command on the table the cursor is in, aligning it in the process.
+///
+/// Returns None if the cursor is not in a table.
+pub fn run(text: &str, cursor: usize, command: Command) -> Option<Change> {
+	let lines = text.split('\n').collect::<Vec<&str>>();
+	let kinds = block::classify(text);
+
+	let line = text[..cursor].matches('\n').count();
+	if kinds.get(line) != Some(&Kind::Table) {
+		return None;
+	}
+
+	let first = (0..=line)
+		.rev()
+		.take_while(|i| kinds[*i] == Kind::Table)
+		.last()?;
+	let last = (line..lines.len())
+		.take_while(|i| kinds[*i] == Kind::Table)
+		.last()?;
+
+	let start = lines[..first]
+		.iter()
+		.map(|line| line.len() + 1)
+		.sum::<usize>();
+	let end = start + lines[first..=last].join("\n").len();
+
+	let mut table = Table::parse(&lines[first..=last]);
+
+	let line_start = start
+		+ lines[first..line]
+			.iter()
+			.map(|line| line.len() + 1)
+			.sum::<usize>();
+
+	// The delimiter row is treated as part of the header
+	let row = (line - first).saturating_sub(1);
+	let column = table.column_at(lines[line], cursor - line_start);
+	let (row, column) = table.run(command, row, column);
+
+	let (rendered, cells) = table.render();
+	let cursor = start + cells[row][column];
+
+	Some(Change {
+		text: format!("{}{rendered}{}", &text[..start], &text[end..]),
+		selection: cursor..cursor,
+	})
+}
+
+/// Lines up the columns of the table spanning the lines
+pub fn align(lines: &[&str]) -> String {
+	Table::parse(lines).render().0
+}
+
+/// Converts tab or comma separated values into a table.
+///
+/// Returns None if the text does not look like it contains any.
+pub fn from_delimited(text: &str) -> Option<String> {
+	let lines = text
+		.trim_end()
+		.lines()
+		.filter(|line| !line.trim().is_empty())
+		.collect::<Vec<&str>>();
+
+	if lines.len() < 2 {
+		return None;
+	}
+
+	let rows = [split_tsv, split_csv].into_iter().find_map(|split| {
+		let rows = lines
+			.iter()
+			.map(|line| split(line))
+			.collect::<Vec<Vec<String>>>();
+		let columns = rows[0].len();
+		(columns > 1 && rows.iter().all(|row| row.len() == columns)).then_some(rows)
+	})?;
+
+	let table = Table {
+		prefix: String::new(),
+		aligns: vec![Align::None; rows[0].len()],
+		rows: rows
+			.into_iter()
+			.map(|row| {
+				row.into_iter()
+					.map(|cell| cell.replace('|', "\\|"))
+					.collect()
+			})
+			.collect(),
+	};
+
+	Some(table.render().0)
+}
+
+fn split_tsv(line: &str) -> Vec<String> {
+	line.split('\t')
+		.map(|cell| cell.trim().to_string())
+		.collect()
+}
+
+/// Splits a line of comma separated values, taking quoted values into account
+fn split_csv(line: &str) -> Vec<String> {
+	let mut cells = vec![String::new()];
+	let mut quoted = false;
+	let mut chars = line.chars().peekable();
+
+	while let Some(ch) = chars.next() {
+		let cell = cells.last_mut().expect("There is always at least one cell");
+
+		match ch {
+			'"' if quoted && chars.peek() == Some(&'"') => {
+				cell.push('"');
+				chars.next();
+			}
+			'"' => quoted = !quoted,
+			',' if !quoted => cells.push(String::new()),
+			ch => cell.push(ch),
+		}
+	}
+
+	cells
+		.into_iter()
+		.map(|cell| cell.trim().to_string())
+		.collect()
+}
+
+impl Table {
+	fn parse(lines: &[&str]) -> Self {
+		let parts = block::split(lines[0]);
+		let prefix = format!("{}{}", parts.quote, parts.indent);
+
+		let mut rows = lines
+			.iter()
+			.map(|line| cells(block::strip(line)))
+			.collect::<Vec<Vec<String>>>();
+
+		let mut aligns = if lines.len() > 1 && block::is_delimiter_row(block::strip(lines[1])) {
+			rows.remove(1)
+				.iter()
+				.map(|cell| Align::parse(cell))
+				.collect()
+		} else {
+			vec![]
+		};
+
+		let columns = rows.iter().map(Vec::len).max().unwrap_or_default().max(1);
+		for row in &mut rows {
+			row.resize(columns, String::new());
+		}
+		aligns.resize(columns, Align::None);
+
+		Self {
+			prefix,
+			aligns,
+			rows,
+		}
+	}
+
+	fn columns(&self) -> usize {
+		self.aligns.len()
+	}
+
+	/// The column of the cell at the byte column of a line
+	fn column_at(&self, line: &str, column: usize) -> usize {
+		let row = block::strip(line);
+		let prefix = line.len() - row.len();
+		let before = &row[..column.saturating_sub(prefix).min(row.len())];
+
+		let pipes = pipes(before).len();
+		let pipes = if row.starts_with('|') {
+			pipes.saturating_sub(1)
+		} else {
+			pipes
+		};
+
+		pipes.min(self.columns() - 1)
+	}
+
+	/// Runs the command, returning the row and column the cursor ends up in
+	fn run(&mut self, command: Command, row: usize, column: usize) -> (usize, usize) {
+		let columns = self.columns();
+		let empty = vec![String::new(); columns];
+
+		match command {
+			Command::Align => (row, column),
+
+			Command::NextCell if column + 1 < columns => (row, column + 1),
+			Command::NextCell => {
+				if row + 1 == self.rows.len() {
+					self.rows.push(empty);
+				}
+				(row + 1, 0)
+			}
+
+			Command::PreviousCell if column > 0 => (row, column - 1),
+			Command::PreviousCell if row > 0 => (row - 1, columns - 1),
+			Command::PreviousCell => (row, column),
+
+			Command::NextRow => {
+				if row + 1 == self.rows.len() {
+					self.rows.push(empty);
+				}
+				(row + 1, column)
+			}
+
+			Command::InsertRow => {
+				self.rows.insert(row + 1, empty);
+				(row + 1, column)
+			}
+
+			// The header cannot be removed
+			Command::DeleteRow if row == 0 => (row, column),
+			Command::DeleteRow => {
+				self.rows.remove(row);
+				(row.min(self.rows.len() - 1), column)
+			}
+
+			Command::InsertColumn => {
+				for cells in &mut self.rows {
+					cells.insert(column + 1, String::new());
+				}
+				self.aligns.insert(column + 1, Align::None);
+				(row, column + 1)
+			}
+
+			Command::DeleteColumn if columns == 1 => (row, column),
+			Command::DeleteColumn => {
+				for cells in &mut self.rows {
+					cells.remove(column);
+				}
+				self.aligns.remove(column);
+				(row, column.min(columns - 2))
+			}
+
+			Command::Sort => {
+				let body = &mut self.rows[1..];
+
+				let numeric = body
+					.iter()
+					.all(|cells| cells[column].parse::<f64>().is_ok());
+
+				let compare = |a: &Vec<String>, b: &Vec<String>| {
+					if numeric {
+						let a = a[column].parse::<f64>().unwrap_or_default();
+						let b = b[column].parse::<f64>().unwrap_or_default();
+						a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+					} else {
+						a[column].to_lowercase().cmp(&b[column].to_lowercase())
+					}
+				};
+
+				if body.is_sorted_by(|a, b| compare(a, b) != Ordering::Greater) {
+					body.sort_by(|a, b| compare(b, a));
+				} else {
+					body.sort_by(compare);
+				}
+				(row, column)
+			}
+		}
+	}
+
+	/// Renders the table with its columns lined up.
+	///
+	/// Also returns the byte offset of the text of every cell, with the rows matching [Self::rows].
+	fn render(&self) -> (String, Vec<Vec<usize>>) {
+		let widths = (0..self.columns())
+			.map(|column| {
+				self.rows
+					.iter()
+					.map(|cells| cells[column].chars().count())
+					.max()
+					.unwrap_or_default()
+					.max(3)
+			})
+			.collect::<Vec<usize>>();
+
+		let delimiters = self
+			.aligns
+			.iter()
+			.zip(&widths)
+			.map(|(align, width)| align.delimiter(*width))
+			.collect::<Vec<String>>();
+
+		let mut lines = vec![];
+		let mut offsets = vec![];
+		let mut len = 0;
+
+		for (i, cells) in self.rows.iter().enumerate() {
+			let (line, starts) = self.line(cells, &widths);
+			offsets.push(starts.into_iter().map(|start| len + start).collect());
+			len += line.len() + 1;
+			lines.push(line);
+
+			// The delimiter row comes right after the header
+			if i == 0 {
+				let (line, _) = self.line(&delimiters, &widths);
+				len += line.len() + 1;
+				lines.push(line);
+			}
+		}
+
+		(lines.join("\n"), offsets)
+	}
+
+	/// Renders a single row, returning it along with the byte offset of the text of each cell
+	fn line(&self, cells: &[String], widths: &[usize]) -> (String, Vec<usize>) {
+		let mut line = self.prefix.clone();
+		let mut starts = vec![];
+
+		for ((cell, width), align) in cells.iter().zip(widths).zip(&self.aligns) {
+			let padding = width - cell.chars().count();
+			let (left, right) = match align {
+				Align::Right => (padding, 0),
+				Align::Center => (padding / 2, padding - padding / 2),
+				Align::None | Align::Left => (0, padding),
+			};
+
+			line.push_str("| ");
+			line.push_str(&" ".repeat(left));
+			starts.push(line.len());
+			line.push_str(cell);
+			line.push_str(&" ".repeat(right + 1));
+		}
+
+		line.push('|');
+		(line, starts)
+	}
+}
+
+impl Align {
+	fn parse(delimiter: &str) -> Self {
+		match (delimiter.starts_with(':'), delimiter.ends_with(':')) {
+			(true, true) => Self::Center,
+			(true, false) => Self::Left,
+			(false, true) => Self::Right,
+			(false, false) => Self::None,
+		}
+	}
+
+	fn delimiter(&self, width: usize) -> String {
+		match self {
+			Self::None => "-".repeat(width),
+			Self::Left => format!(":{}", "-".repeat(width - 1)),
+			Self::Center => format!(":{}:", "-".repeat(width - 2)),
+			Self::Right => format!("{}:", "-".repeat(width - 1)),
+		}
+	}
+}
+
+/// Splits a row into its cells, without the pipes at the edges
+fn cells(row: &str) -> Vec<String> {
+	let row = row.trim();
+	let pipes = pipes(row);
+
+	let mut ranges: Vec<Range<usize>> = vec![];
+	let mut start = 0;
+	for pipe in pipes.iter().copied().chain([row.len()]) {
+		ranges.push(start..pipe);
+		start = pipe + 1;
+	}
+
+	// Pipes at the edges do not start new cells
+	if row.starts_with('|') {
+		ranges.remove(0);
+	}
+	if pipes.last().is_some_and(|pipe| pipe + 1 == row.len()) {
+		ranges.pop();
+	}
+
+	ranges
+		.into_iter()
+		.map(|range| row[range].trim().to_string())
+		.collect()
+}
+
+/// The byte offsets of the pipes separating cells.
+///
+/// Like GFM, only escaped pipes are skipped, even within code.
+fn pipes(row: &str) -> Vec<usize> {
+	let mut pipes = vec![];
+	let mut escaped = false;
+
+	for (i, ch) in row.char_indices() {
+		if ch == '|' && !escaped {
+			pipes.push(i);
+		}
+		escaped = ch == '\\' && !escaped;
+	}
+
+	pipes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TABLE: &str = "| a | b |\n|-|-|\n| 2 | d |\n| 1 | e |";
+
+	#[test]
+	fn aligns_columns() {
+		assert_eq!(
+			align(&["| a | bb |", "|:-|-:|", "| ccc | d |"]),
+			"| a   |  bb |\n| :-- | --: |\n| ccc |   d |"
+		);
+	}
+
+	#[test]
+	fn converts_delimited_values() {
+		assert_eq!(
+			from_delimited("name,quote\nA,\"x, \"\"y\"\"\"\nB,a|b\n").as_deref(),
+			Some("| name | quote  |\n| ---- | ------ |\n| A    | x, \"y\" |\n| B    | a\\|b   |")
+		);
+		assert_eq!(
+			from_delimited("a\tb\nc\td").as_deref(),
+			Some("| a   | b   |\n| --- | --- |\n| c   | d   |")
+		);
+
+		assert_eq!(from_delimited("just text\nmore text"), None);
+		assert_eq!(from_delimited("a,b\nc"), None);
+		assert_eq!(from_delimited("a,b"), None);
+	}
+
+	#[test]
+	fn runs_commands() {
+		let cursor = TABLE.len() - 2;
+		let run_last = |command| run(TABLE, cursor, command).unwrap();
+
+		let next = run_last(Command::NextCell);
+		assert_eq!(
+			next.text,
+			"| a   | b   |\n| --- | --- |\n| 2   | d   |\n| 1   | e   |\n|     |     |"
+		);
+		assert_eq!(next.selection, 58..58);
+
+		let sorted = run_last(Command::Sort);
+		assert_eq!(
+			sorted.text,
+			"| a   | b   |\n| --- | --- |\n| 1   | e   |\n| 2   | d   |"
+		);
+
+		let deleted = run_last(Command::DeleteRow);
+		assert_eq!(deleted.text, "| a   | b   |\n| --- | --- |\n| 2   | d   |");
+	}
+
+	#[test]
+	fn ignores_text_outside_tables() {
+		assert!(run("text\n\n| a |", 1, Command::Align).is_none());
+	}
+
+	#[test]
+	fn handles_malformed_tables() {
+		let texts = [
+			"|",
+			"||",
+			"| a |",
+			"| a |\n|",
+			"| a | b |\n| - |",
+			"| a |\n|-|-|-|\n| b | c | d | e |",
+			"| é | ü |\n|:-:|--|\n| ß |",
+			"> | a |\n> |-|\n> | b |",
+			"a | b\n- | -\nc",
+			"| a \\| b |\n|---|\n| `|` |",
+		];
+		let commands = [
+			Command::Align,
+			Command::NextCell,
+			Command::PreviousCell,
+			Command::NextRow,
+			Command::InsertRow,
+			Command::DeleteRow,
+			Command::InsertColumn,
+			Command::DeleteColumn,
+			Command::Sort,
+		];
+
+		for text in texts {
+			for cursor in (0..=text.len()).filter(|i| text.is_char_boundary(*i)) {
+				for command in commands {
+					if let Some(change) = run(text, cursor, command) {
+						let end = change.selection.end;
+						assert!(change.text.is_char_boundary(end), "{text:?} {command:?}");
+					}
+				}
+			}
+			align(&text.split('\n').collect::<Vec<_>>());
+		}
+	}
+}
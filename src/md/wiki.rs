@@ -0,0 +1,118 @@
+//! Wiki links, which link to other notes by name with `[[Note]]` or `[[Note|alias]]`
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd, TextMergeWithOffset};
+
+/// A link to another note, written as `[[note#anchor|alias]]`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WikiLink {
+	/// The byte range of the link, including its brackets
+	pub range: Range<usize>,
+	/// The name of the note, which may be a path relative to the workspace
+	pub note: String,
+	/// The anchor of a heading of the note
+	pub anchor: Option<String>,
+	/// The text shown instead of the name of the note
+	pub alias: Option<String>,
+}
+
+impl WikiLink {
+	/// The text the link is shown as
+	pub fn label(&self) -> String {
+		match (&self.alias, &self.anchor) {
+			(Some(alias), _) => alias.clone(),
+			(None, Some(anchor)) if self.note.is_empty() => anchor.clone(),
+			(None, Some(anchor)) => format!("{} > {anchor}", self.note),
+			(None, None) => self.note.clone(),
+		}
+	}
+}
+
+/// The wiki links within the prose of the text, leaving out code
+pub fn links(text: &str) -> Vec<WikiLink> {
+	let mut links = vec![];
+	// How many code and metadata blocks the text is in
+	let mut verbatim = 0;
+
+	let events =
+		TextMergeWithOffset::new(Parser::new_ext(text, super::EXTENSIONS).into_offset_iter());
+	for (event, range) in events {
+		match event {
+			Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => verbatim += 1,
+			Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => verbatim -= 1,
+
+			Event::Text(_) if verbatim == 0 => {
+				links.extend(find(&text[range.clone()]).into_iter().map(|link| WikiLink {
+					range: range.start + link.range.start..range.start + link.range.end,
+					..link
+				}));
+			}
+
+			_ => (),
+		}
+	}
+
+	links
+}
+
+/// The wiki links within a piece of text, which cannot span multiple lines
+pub fn find(text: &str) -> Vec<WikiLink> {
+	let mut links = vec![];
+	let mut start = 0;
+
+	while let Some(open) = text[start..].find("[[").map(|i| start + i) {
+		let inner = open + 2;
+		let Some(close) = text[inner..].find("]]").map(|i| inner + i) else {
+			break;
+		};
+
+		match parse(&text[inner..close]) {
+			Some((note, anchor, alias)) => {
+				links.push(WikiLink {
+					range: open..close + 2,
+					note,
+					anchor,
+					alias,
+				});
+				start = close + 2;
+			}
+			// Another link may start within the brackets
+			None => start = open + 1,
+		}
+	}
+
+	links
+}
+
+/// Splits the text between the brackets of a link into its note, anchor and alias
+fn parse(inner: &str) -> Option<(String, Option<String>, Option<String>)> {
+	if inner.contains(['[', ']', '\n']) {
+		return None;
+	}
+
+	let (target, alias) = match inner.split_once('|') {
+		Some((target, alias)) => (target, Some(alias.trim().to_string())),
+		None => (inner, None),
+	};
+	let (note, anchor) = match target.split_once('#') {
+		Some((note, anchor)) => (note, Some(anchor.trim().to_string())),
+		None => (target, None),
+	};
+
+	let note = note.trim().to_string();
+	let anchor = anchor.filter(|anchor| !anchor.is_empty());
+	if note.is_empty() && anchor.is_none() {
+		return None;
+	}
+
+	Some((note, anchor, alias.filter(|alias| !alias.is_empty())))
+}
+
+/// The name of the note being typed, if the text before the cursor ends within an unclosed `[[`
+pub fn completion(before: &str) -> Option<&str> {
+	let line = before.rsplit('\n').next().unwrap_or_default();
+	let partial = &line[line.rfind("[[")? + 2..];
+
+	(!partial.contains(['[', ']', '|', '#'])).then_some(partial)
+}
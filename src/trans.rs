@@ -24,6 +24,13 @@ pub fn init() -> AppResult<()> {
 	Ok(())
 }
 
+/// The language the system prefers, such as "en-US", even if the app has not been translated to it
+pub fn system_language() -> String {
+	DesktopLanguageRequester::requested_languages()
+		.first()
+		.map_or_else(|| "en-US".into(), ToString::to_string)
+}
+
 #[macro_export]
 macro_rules! trans {
 	($message_id:literal) => {{
@@ -4,6 +4,9 @@ use tracing::error;
 
 pub mod cfg;
 pub mod init;
+pub mod links;
+pub mod notes;
+pub mod spell;
 
 pub type AppResult<Ok> = Result<Ok, Box<dyn Error + Send + Sync>>;
 
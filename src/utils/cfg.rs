@@ -1,4 +1,4 @@
-use serde::de::DeserializeOwned;
+use serde::{Serialize, de::DeserializeOwned};
 use std::{
 	fs,
 	path::{Path, PathBuf},
@@ -7,11 +7,12 @@ use tracing::{error, info};
 
 use crate::pipe;
 
-use super::dir_exists_or_run;
+use super::{dir_exists_or_run, ok_or_default};
 
 pub mod flags;
 pub mod recent;
 pub mod script;
+pub mod zoom;
 
 pub trait DefaultBytes {
 	fn default_bytes() -> impl AsRef<[u8]>;
@@ -68,3 +69,41 @@ where
 		|> maybe!(bincode::deserialize(&out); "Error deserializing file");
 	]
 }
+
+pub fn serialize_or_log<Dir, Cfg>(path: Dir, cfg: &Cfg)
+where
+	Dir: AsRef<Path>,
+	Cfg: Serialize,
+{
+	macro_rules! maybe {
+		($in:expr; $err:literal) => {
+			match $in {
+				Ok(ok) => ok,
+				Err(e) => {
+					error!("{}: {e}", $err);
+					return;
+				}
+			}
+		};
+	}
+
+	pipe! [
+		bytes: maybe!(bincode::serialize(cfg); "Error serializing file");
+		|> maybe!(zstd::bulk::compress(&bytes, 0); "Error encoding file");
+		|> maybe!(fs::write(path, bytes); "Error writing file");
+	]
+}
+
+/// Serializes the default value of a type, to be used with [DefaultBytes]
+pub fn default_bytes<Cfg>() -> Vec<u8>
+where
+	Cfg: Serialize + Default,
+{
+	pipe! [
+		bytes: Cfg::default();
+		|> bincode::serialize(&bytes);
+		|> ok_or_default(bytes);
+		|> zstd::bulk::compress(&bytes, 0);
+		|> ok_or_default(bytes);
+	]
+}
@@ -1,15 +1,34 @@
-use std::collections::HashMap;
+use std::{
+	collections::{HashMap, HashSet},
+	path::{Path, PathBuf},
+};
 
 use cosmic::{
 	iced::{highlighter::Theme, theme::Palette},
 	widget::menu,
 };
-use rhai::{Array, CustomType, FnPtr, Map, TypeBuilder};
+use rhai::{Array, CustomType, Dynamic, FnPtr, Map, TypeBuilder};
 use tracing::warn;
 
 use crate::{
-	app::message::MenuActions,
-	utils::{cfg::script::keybinds::Keybind, ok_or_default},
+	app::{
+		message::MenuActions,
+		state::editor::{
+			attach::Drop,
+			gutter::{LineNumbers, Wrap},
+			status::Field,
+		},
+	},
+	md::lint::Rule,
+	utils::{
+		cfg::script::{
+			fmt::FormatStyle,
+			font::Font,
+			keybinds::{Key, Keybind, modifiers},
+			preview::PreviewStyle,
+		},
+		ok_or_default,
+	},
 };
 
 #[derive(Clone, CustomType)]
@@ -21,6 +40,17 @@ pub struct Flags {
 	pub tab_len: i64,
 	/// How many recently accessed files should be shown on the home screen
 	pub max_recents: i64,
+	/// The largest image file shown in the preview, in megabytes
+	pub max_image_size: f32,
+	/// The commands rendering diagram code blocks to SVG, mapped from the language of the block
+	/// The source is passed through stdin, and the SVG is read from stdout
+	/// Graphviz diagrams are rendered without a command when possible, and no others are set by
+	/// default since they run external programs, such as "mmdc --input - --output -" for Mermaid
+	pub diagram_commands: Map,
+	/// How many steps can be undone
+	pub undo_limit: i64,
+	/// Saves the undo history of files, so that it is kept after closing and reopening them
+	pub persist_history: bool,
 	/// Converts tabs into spaces
 	/// Strongly discouraged
 	pub expand_tabs: bool,
@@ -28,11 +58,100 @@ pub struct Flags {
 	pub highlight: String,
 	/// The palette used by the markdown renderer
 	pub palette: Palette,
-	/// Keybinds used throughout the app
+	/// How the elements of the preview are styled, such as the size of headings
+	pub preview_style: PreviewStyle,
+	/// The font used by the editor
+	pub editor_font: Font,
+	/// The font used by the body text of the preview
+	pub preview_font: Font,
+	/// The font used by headings in the preview
+	pub heading_font: Font,
+	/// The font used by code in the preview
+	pub code_font: Font,
+	/// How much the text is scaled by each time it is zoomed
+	pub zoom_step: f32,
+	/// Gives each tab its own zoom level, rather than sharing one between all of them
+	/// Zoom levels are saved per-file if enabled
+	pub zoom_per_tab: bool,
+	/// How line numbers are shown next to the editor
+	/// Either "none", "absolute", or "relative"
+	pub line_numbers: String,
+	/// Shows markers next to lines containing an occurrence of the selected text, or a problem
+	pub markers: bool,
+	/// Highlights the line the cursor is on
+	pub highlight_line: bool,
+	/// How text in the editor is wrapped
+	/// Either "none" (the default), "window", or "column"
+	/// Line numbers, markers, the line highlight, underlines of problems, misspellings and wiki
+	/// links, and extra cursors are only shown if set to "none", since where wrapped lines break
+	/// is not known
+	/// They also need a monospace editor font, since the text is not measured, and without one
+	/// lines wider than the editor are cut off when set to "none"
+	pub wrap: String,
+	/// The column text is wrapped after
+	/// Only used if "wrap" is set to "column"
+	pub wrap_column: i64,
+	/// The column a vertical ruler is shown after
+	/// Disabled if set to 0
+	pub ruler: i64,
+	/// The column paragraphs are wrapped after when reflowing them
+	/// Joins every paragraph into a single line if set to 0
+	pub reflow_width: i64,
+	/// Characters that are typed in pairs, mapped to the character closing them
+	/// Typing one with text selected surrounds the selection instead
+	pub auto_pairs: Map,
+	/// Turns pasted tab or comma separated values into a table
+	pub paste_tables: bool,
+	/// Where pasted images are saved, relative to the document
+	/// "{file}" is replaced with the name of the document, "{name}" with the name of the image, and
+	/// "{n}" with the first number that is not taken
+	pub image_path: String,
+	/// What happens to image files dropped onto the editor
+	/// Either "copy" (to "image_path") or "link"
+	pub drop_images: String,
+	/// The folder of notes that wiki links such as "[[Note]]" point into
+	/// Left empty to use the folder of the document
+	pub workspace: String,
+	/// The style documents are formatted to
+	pub format_style: FormatStyle,
+	/// Formats documents before saving them
+	pub format_on_save: bool,
+	/// Underlines words that are not in the dictionary
+	pub spell_check: bool,
+	/// The language of the dictionary, such as "en-US"
+	/// Left empty to use the language of the system
+	/// Documents can set their own with a "lang" field in their front matter
+	pub spell_language: String,
+	/// The fields shown in the status bar below the editor, in order
+	/// Any of "words", "characters", "lines", "reading_time", "selection", "cursor", "encoding",
+	/// and "line_ending"
+	/// The status bar is hidden if left empty
+	pub status_bar: Array,
+	/// How many words are read per minute, used to estimate the reading time
+	pub reading_speed: i64,
+	/// Checks documents for common problems while editing them
+	/// The lint command checks them either way
+	pub lint: bool,
+	/// Whether each check is enabled, by name
+	/// Checks left out are enabled
+	pub lint_rules: Map,
+	/// Overrides "lint_rules" for the files within a directory, mapping the directory to the rules
+	/// The most specific directory is used
+	pub lint_projects: Map,
+	/// Requests external URLs when checking links, reporting the ones that cannot be reached
+	/// Slow, since every URL is requested
+	pub check_external_links: bool,
+	/// Keybinds used throughout the app, added to the default ones
+	/// A keybind using the same keys as a default one replaces it
 	pub general_keybinds: Array,
 	/// Function ran when starting the app
 	/// Mostly useful for debugging purposes
 	pub callback: FnPtr,
+	/// Function ran with the front matter of a document as a map, whenever it is opened or its
+	/// front matter changes
+	/// The map it returns is shown in the metadata panel
+	/// Only ran if the script defines it
+	pub front_matter_hook: FnPtr,
 }
 
 impl Flags {
@@ -55,6 +174,131 @@ impl Flags {
 		}
 	}
 
+	pub fn line_numbers(&self) -> LineNumbers {
+		match self.line_numbers.to_lowercase().trim() {
+			"none" => LineNumbers::None,
+			"absolute" => LineNumbers::Absolute,
+			"relative" => LineNumbers::Relative,
+
+			unknown => {
+				warn!("Line number style {unknown} not found");
+				LineNumbers::None
+			}
+		}
+	}
+
+	pub fn wrap(&self) -> Wrap {
+		match self.wrap.to_lowercase().trim() {
+			"none" => Wrap::None,
+			"window" => Wrap::Window,
+			"column" => Wrap::Column(ok_or_default(usize::try_from(self.wrap_column)).max(1)),
+
+			unknown => {
+				warn!("Wrap style {unknown} not found");
+				Wrap::Window
+			}
+		}
+	}
+
+	pub fn ruler(&self) -> Option<usize> {
+		usize::try_from(self.ruler).ok().filter(|ruler| *ruler > 0)
+	}
+
+	pub fn reflow_width(&self) -> usize {
+		ok_or_default(usize::try_from(self.reflow_width))
+	}
+
+	pub fn auto_pairs(&self) -> HashMap<char, char> {
+		fn single(str: &str) -> Option<char> {
+			let mut chars = str.chars();
+			chars.next().filter(|_| chars.next().is_none())
+		}
+
+		self.auto_pairs
+			.iter()
+			.filter_map(|(open, close)| {
+				let close = close.clone().into_string().unwrap_or_default();
+
+				match (single(open), single(&close)) {
+					(Some(open), Some(close)) => Some((open, close)),
+					_ => {
+						warn!(
+							"Auto pair \"{open}\" and \"{close}\" must both be single characters"
+						);
+						None
+					}
+				}
+			})
+			.collect()
+	}
+
+	/// The lint rules enabled for the file, even if linting while editing is disabled
+	pub fn lint_rules(&self, path: Option<&Path>) -> HashSet<Rule> {
+		fn toggles(map: &Map) -> Vec<(Rule, bool)> {
+			map.iter()
+				.filter_map(|(name, enabled)| {
+					let Some(rule) = Rule::from_name(name) else {
+						warn!("Lint rule {name} not found");
+						return None;
+					};
+					match enabled.as_bool() {
+						Ok(enabled) => Some((rule, enabled)),
+						Err(e) => {
+							warn!("Lint rule {name} must be enabled with a bool, not {e}");
+							None
+						}
+					}
+				})
+				.collect()
+		}
+
+		let mut rules = Rule::ALL.into_iter().collect::<HashSet<Rule>>();
+		let mut apply = |map: &Map| {
+			for (rule, enabled) in toggles(map) {
+				if enabled {
+					rules.insert(rule);
+				} else {
+					rules.remove(&rule);
+				}
+			}
+		};
+
+		apply(&self.lint_rules);
+
+		let project = path.and_then(|path| {
+			self.lint_projects
+				.iter()
+				.filter(|(dir, _)| path.starts_with(dir.as_str()))
+				.max_by_key(|(dir, _)| dir.len())
+		});
+		if let Some((dir, project)) = project {
+			match project.clone().try_cast::<Map>() {
+				Some(project) => apply(&project),
+				None => warn!("Lint rules of project {dir} must be a map"),
+			}
+		}
+
+		rules
+	}
+
+	pub fn status_bar(&self) -> Vec<Field> {
+		self.status_bar
+			.iter()
+			.filter_map(|field| {
+				let name = field.clone().into_string().unwrap_or_default();
+				let field = Field::parse(&name);
+				if field.is_none() {
+					warn!("Status bar field {name} not found");
+				}
+				field
+			})
+			.collect()
+	}
+
+	pub fn reading_speed(&self) -> usize {
+		ok_or_default(usize::try_from(self.reading_speed))
+	}
+
 	pub fn tab_len(&self) -> usize {
 		ok_or_default(usize::try_from(self.tab_len))
 	}
@@ -63,12 +307,53 @@ impl Flags {
 		ok_or_default(usize::try_from(self.max_recents))
 	}
 
+	pub fn drop_images(&self) -> Drop {
+		match self.drop_images.to_lowercase().trim() {
+			"copy" => Drop::Copy,
+			"link" => Drop::Link,
+			unknown => {
+				warn!("Drop mode {unknown} not found");
+				Drop::Copy
+			}
+		}
+	}
+
+	/// The folder of notes that wiki links of the document point into, if it has one
+	pub fn workspace(&self, document: Option<&Path>) -> Option<PathBuf> {
+		let workspace = self.workspace.trim();
+
+		if workspace.is_empty() {
+			document.and_then(Path::parent).map(Path::to_path_buf)
+		} else if let Some(workspace) = workspace.strip_prefix("~/") {
+			dirs::home_dir().map(|home| home.join(workspace))
+		} else {
+			Some(PathBuf::from(workspace))
+		}
+	}
+
+	/// The largest image file shown in the preview, in bytes
+	pub fn max_image_size(&self) -> u64 {
+		(self.max_image_size.max(0.) * 1024. * 1024.) as u64
+	}
+
+	/// The command rendering diagrams of the language, if one is set
+	pub fn diagram_command(&self, language: &str) -> Option<String> {
+		self.diagram_commands
+			.get(language)
+			.and_then(|command| command.clone().into_string().ok())
+			.filter(|command| !command.trim().is_empty())
+	}
+
+	pub fn undo_limit(&self) -> usize {
+		ok_or_default(usize::try_from(self.undo_limit))
+	}
+
 	pub fn general_keybinds(&self) -> HashMap<menu::KeyBind, MenuActions> {
 		let mut keybinds = HashMap::new();
 
-		let map_array = self
-			.general_keybinds
+		let map_array = default_keybinds()
 			.iter()
+			.chain(&self.general_keybinds)
 			.filter_map(|item| match item.as_map_ref() {
 				Ok(map) => Some(map.clone()),
 				Err(e) => {
@@ -111,10 +396,102 @@ impl Default for Flags {
 			tab_len: 4,
 			expand_tabs: false,
 			max_recents: 8,
+			max_image_size: 20.,
+			diagram_commands: Map::new(),
+			undo_limit: 1000,
+			persist_history: false,
 			highlight: "base16eighties".into(),
 			palette: Palette::CATPPUCCIN_FRAPPE,
+			preview_style: PreviewStyle::default(),
+			editor_font: Font::default(),
+			preview_font: Font::default(),
+			heading_font: Font {
+				weight: 700,
+				..Font::default()
+			},
+			code_font: Font::default(),
+			zoom_step: 0.1,
+			zoom_per_tab: false,
+			line_numbers: "none".into(),
+			markers: false,
+			highlight_line: false,
+			wrap: "none".into(),
+			wrap_column: 80,
+			ruler: 0,
+			reflow_width: 80,
+			auto_pairs: default_auto_pairs(),
+			paste_tables: true,
+			image_path: "assets/{file}-{n}.png".into(),
+			drop_images: "copy".into(),
+			workspace: String::new(),
+			format_style: FormatStyle::default(),
+			format_on_save: false,
+			spell_check: false,
+			spell_language: String::new(),
+			status_bar: default_status_bar(),
+			reading_speed: 200,
+			lint: false,
+			lint_rules: Map::new(),
+			lint_projects: Map::new(),
+			check_external_links: false,
 			callback: FnPtr::new("callback").unwrap(),
+			front_matter_hook: FnPtr::new("front_matter").unwrap(),
 			general_keybinds: vec![],
 		}
 	}
 }
+
+fn default_status_bar() -> Array {
+	[
+		"words",
+		"characters",
+		"reading_time",
+		"selection",
+		"cursor",
+		"encoding",
+		"line_ending",
+	]
+	.into_iter()
+	.map(|field| Dynamic::from(field.to_string()))
+	.collect()
+}
+
+fn default_auto_pairs() -> Map {
+	[
+		("*", "*"),
+		("_", "_"),
+		("`", "`"),
+		("[", "]"),
+		("(", ")"),
+		("\"", "\""),
+	]
+	.into_iter()
+	.map(|(open, close)| (open.into(), Dynamic::from(close.to_string())))
+	.collect()
+}
+
+fn default_keybinds() -> Array {
+	let keybinds = [
+		(MenuActions::Bold, "b"),
+		(MenuActions::Italic, "i"),
+		(MenuActions::Link, "k"),
+		(MenuActions::Undo, "z"),
+		(MenuActions::Redo, "y"),
+		(MenuActions::AddNextOccurrence, "d"),
+		(MenuActions::ZoomIn, "="),
+		(MenuActions::ZoomOut, "-"),
+		(MenuActions::ZoomReset, "0"),
+	];
+
+	keybinds
+		.into_iter()
+		.map(|(action, key)| {
+			let keybind = Keybind::new(Key::new(key.into()), vec![Dynamic::from(modifiers::Ctrl)]);
+
+			let mut map = Map::new();
+			map.insert("action".into(), Dynamic::from(action));
+			map.insert("keybind".into(), Dynamic::from(keybind));
+			Dynamic::from_map(map)
+		})
+		.collect()
+}
@@ -1,11 +1,11 @@
 use std::{path::PathBuf, vec::IntoIter};
 
 use serde::{Deserialize, Serialize};
-use tracing::error;
 
-use crate::{pipe, utils::ok_or_default};
-
-use super::{DefaultBytes, deserialize_or_default, flags::Flags, get_or_create_cfg_file};
+use super::{
+	DefaultBytes, default_bytes, deserialize_or_default, flags::Flags, get_or_create_cfg_file,
+	serialize_or_log,
+};
 
 pub const DIR: &str = ".recents";
 
@@ -27,25 +27,7 @@ impl Recent {
 	}
 
 	pub fn write(&self) {
-		macro_rules! maybe {
-			($in:expr) => {
-				match $in {
-					Ok(ok) => ok,
-					Err(e) => {
-						error!("{e}");
-						return;
-					}
-				}
-			};
-		}
-
-		let dir = get_or_create_cfg_file::<_, Self>(DIR);
-
-		pipe! [
-			bytes: maybe!(bincode::serialize(&self));
-			|> maybe!(zstd::bulk::compress(&bytes, 0));
-			|> maybe!(std::fs::write(dir, bytes));
-		]
+		serialize_or_log(get_or_create_cfg_file::<_, Self>(DIR), self);
 	}
 
 	pub fn get_inner(&self) -> &[PathBuf] {
@@ -55,13 +37,7 @@ impl Recent {
 
 impl DefaultBytes for Recent {
 	fn default_bytes() -> impl AsRef<[u8]> {
-		pipe! [
-			bytes: Self::default();
-			|> bincode::serialize(&bytes);
-			|> ok_or_default(bytes);
-			|> zstd::bulk::compress(&bytes, 0);
-			|> ok_or_default(bytes);
-		]
+		default_bytes::<Self>()
 	}
 }
 
@@ -1,14 +1,26 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use rhai::{AST, Engine, FnPtr, FuncArgs};
 
-use crate::utils::{AppResult, ok_or_default};
+use crate::{
+	md::fmt::Options,
+	utils::{AppResult, ok_or_default},
+};
 
-use super::{DefaultBytes, flags::Flags};
+use super::{
+	DefaultBytes,
+	flags::Flags,
+	get_or_create_cfg_file,
+	zoom::{self, Zoom},
+};
 
 pub mod color;
 pub mod engine;
+pub mod fmt;
+pub mod font;
 pub mod keybinds;
+pub mod markdown;
+pub mod preview;
 
 const DEFAULT_CFG: &str = "\
 let flags = flags();
@@ -21,6 +33,13 @@ pub struct ScriptCfg {
 	engine: Engine,
 	ast: AST,
 	pub flags: Flags,
+	/// The characters typed in pairs, parsed from the flags once rather than whenever a key is
+	/// pressed
+	pub auto_pairs: HashMap<char, char>,
+	/// The options of the formatter, checked once rather than whenever a document is formatted
+	pub format_options: Options,
+	/// Not set by the script, but stored here so that every [Screen](crate::app::Screen) can access it
+	pub zoom: Zoom,
 }
 
 impl ScriptCfg {
@@ -28,8 +47,18 @@ impl ScriptCfg {
 		let engine = engine::engine();
 		let ast = ok_or_default(engine.compile_file(path.into()));
 		let flags = ok_or_default(engine.eval_ast::<Flags>(&ast));
+		let auto_pairs = flags.auto_pairs();
+		let format_options = flags.format_style.options();
+		let zoom = Zoom::read(get_or_create_cfg_file::<_, Zoom>(zoom::DIR));
 
-		Self { engine, ast, flags }
+		Self {
+			engine,
+			ast,
+			flags,
+			auto_pairs,
+			format_options,
+			zoom,
+		}
 	}
 
 	pub fn call_rhai_fn<T>(&self, fnptr: FnPtr, args: impl FuncArgs) -> AppResult<T>
@@ -38,6 +67,13 @@ impl ScriptCfg {
 	{
 		Ok(fnptr.call(&self.engine, &self.ast, args)?)
 	}
+
+	/// Checks if the script defines the function, so that hooks can be left out of it
+	pub fn has_rhai_fn(&self, fnptr: &FnPtr) -> bool {
+		self.ast
+			.iter_functions()
+			.any(|function| function.name == fnptr.fn_name())
+	}
 }
 
 impl DefaultBytes for ScriptCfg {
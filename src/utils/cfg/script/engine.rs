@@ -8,7 +8,11 @@ use crate::{
 
 use super::{
 	color::{i64_to_color, palette, palettes, str_to_color},
+	fmt::FormatStyle,
+	font::Font,
 	keybinds::{Key, Keybind, modifiers},
+	markdown,
+	preview::PreviewStyle,
 };
 
 pub fn engine() -> Engine {
@@ -66,7 +70,24 @@ fn register_types(engine: &mut Engine) {
 		// Also used for themes
 		.register_type_with_name::<Palette>("Palette")
 		.register_fn("palette", palette)
-		.register_static_module("Palette", rhai_mod!(palettes));
+		.register_static_module("Palette", rhai_mod!(palettes))
+		// Fonts
+		// Used for the editor and preview
+		.build_type::<Font>()
+		.register_fn("font", Font::new)
+		.register_fn("font", Font::with_style)
+		// Format styles
+		// Used by the formatter
+		.build_type::<FormatStyle>()
+		.register_fn("format_style", FormatStyle::default)
+		// Preview styles
+		// Used by the preview
+		.build_type::<PreviewStyle>()
+		.register_fn("preview_style", PreviewStyle::default)
+		// Markdown
+		// Lets scripts process text the same way the editor does
+		.register_fn("reflow", markdown::reflow)
+		.register_fn("format", markdown::format);
 }
 
 fn misc(engine: &mut Engine) {
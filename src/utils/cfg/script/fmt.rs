@@ -0,0 +1,92 @@
+use rhai::{CustomType, TypeBuilder};
+use tracing::warn;
+
+use crate::{
+	md::fmt::{Heading, Options},
+	utils::ok_or_default,
+};
+
+/// The style documents are formatted to, as set in the config
+#[derive(Clone, CustomType)]
+pub struct FormatStyle {
+	/// Either "atx" or "setext"
+	/// Setext only applies to headings of level 1 and 2
+	pub heading: String,
+	/// The marker of unordered list items
+	/// Either "-", "*", or "+"
+	pub bullet: String,
+	/// Either "*" or "_"
+	pub emphasis: String,
+	/// Either "**" or "__"
+	pub strong: String,
+	/// The delimiter of code blocks
+	/// Either "```" or "~~~"
+	pub fence: String,
+	/// How many blank lines are kept in a row
+	pub blank_lines: i64,
+	/// Lines up the columns of tables
+	pub align_tables: bool,
+	/// Removes whitespace at the end of lines, other than hard breaks
+	pub trim_trailing: bool,
+}
+
+impl FormatStyle {
+	/// Converts the style into the options used by the formatter, warning about invalid values
+	pub fn options(&self) -> Options {
+		let default = Options::default();
+
+		let heading = match self.heading.to_lowercase().trim() {
+			"atx" => Heading::Atx,
+			"setext" => Heading::Setext,
+
+			unknown => {
+				warn!("Heading style {unknown} not found");
+				default.heading
+			}
+		};
+
+		Options {
+			heading,
+			bullet: delimiter("Bullet", &self.bullet, &['-', '*', '+'], 1, default.bullet),
+			emphasis: delimiter("Emphasis", &self.emphasis, &['*', '_'], 1, default.emphasis),
+			strong: delimiter(
+				"Strong emphasis",
+				&self.strong,
+				&['*', '_'],
+				2,
+				default.strong,
+			),
+			fence: delimiter("Fence", &self.fence, &['`', '~'], 3, default.fence),
+			blank_lines: ok_or_default(usize::try_from(self.blank_lines)),
+			align_tables: self.align_tables,
+			trim_trailing: self.trim_trailing,
+		}
+	}
+}
+
+impl Default for FormatStyle {
+	fn default() -> Self {
+		Self {
+			heading: "atx".into(),
+			bullet: "-".into(),
+			emphasis: "*".into(),
+			strong: "**".into(),
+			fence: "```".into(),
+			blank_lines: 1,
+			align_tables: true,
+			trim_trailing: true,
+		}
+	}
+}
+
+/// Finds which of the allowed characters the delimiter is made of, given how many times it is repeated
+fn delimiter(name: &str, value: &str, allowed: &[char], repeat: usize, default: char) -> char {
+	allowed
+		.iter()
+		.copied()
+		.find(|ch| value.trim() == ch.to_string().repeat(repeat))
+		.unwrap_or_else(|| {
+			warn!("{name} \"{value}\" is not valid");
+			default
+		})
+}
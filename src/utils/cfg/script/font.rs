@@ -0,0 +1,171 @@
+use std::sync::{Mutex, OnceLock};
+
+use ahash::HashMap;
+use cosmic::iced::{
+	Font as CosmicFont, Task,
+	font::{self, Family, Weight},
+	widget::text::LineHeight,
+};
+use rhai::{CustomType, TypeBuilder};
+use tracing::warn;
+
+/// The proportional font used when none is set, or the one set is not installed
+pub const SANS: CosmicFont = CosmicFont::with_name("DejaVu Sans");
+/// The monospace font used when none is set, or the one set is not installed
+pub const MONO: CosmicFont = CosmicFont::with_name("DejaVu Sans Mono");
+
+#[derive(rust_embed::RustEmbed)]
+#[folder = "assets/fonts/"]
+struct Bundled;
+
+/// A font, as set in the config
+#[derive(Clone, CustomType)]
+pub struct Font {
+	/// The name of the font family
+	/// Left empty to use the fallback font
+	pub family: String,
+	/// The weight of the font, from 100 to 900
+	pub weight: i64,
+	/// The height of each line, relative to the text size
+	pub line_height: f32,
+}
+
+impl Font {
+	pub fn new(family: String) -> Self {
+		Self {
+			family,
+			..Self::default()
+		}
+	}
+
+	pub fn with_style(family: String, weight: i64, line_height: f32) -> Self {
+		Self {
+			family,
+			weight,
+			line_height,
+		}
+	}
+
+	/// Converts the [Font] into one usable by Cosmic.
+	///
+	/// Uses the family of the fallback if the font is not set or not installed.
+	pub fn to_cosmic(&self, fallback: CosmicFont) -> CosmicFont {
+		let family = match lookup(&self.family) {
+			Some(name) => Family::Name(name),
+			None => fallback.family,
+		};
+
+		CosmicFont {
+			family,
+			weight: weight(self.weight),
+			..fallback
+		}
+	}
+
+	pub fn line_height(&self) -> LineHeight {
+		LineHeight::Relative(self.line_height)
+	}
+
+	/// Checks if every character of the font is as wide as the others, which is assumed of the
+	/// fallback if the font is not set or not installed
+	pub fn is_monospace(&self, fallback: CosmicFont) -> bool {
+		match lookup(&self.family) {
+			Some(name) => installed()
+				.get(&name.to_lowercase())
+				.is_some_and(|family| family.monospace),
+			None => fallback == MONO,
+		}
+	}
+}
+
+impl Default for Font {
+	fn default() -> Self {
+		Self {
+			family: String::new(),
+			weight: 400,
+			line_height: 1.3,
+		}
+	}
+}
+
+fn weight(weight: i64) -> Weight {
+	match weight {
+		..=149 => Weight::Thin,
+		150..=249 => Weight::ExtraLight,
+		250..=349 => Weight::Light,
+		350..=449 => Weight::Normal,
+		450..=549 => Weight::Medium,
+		550..=649 => Weight::Semibold,
+		650..=749 => Weight::Bold,
+		750..=849 => Weight::ExtraBold,
+		850.. => Weight::Black,
+	}
+}
+
+/// Finds an installed font family, returning its name if found.
+///
+/// Results are cached by the lowercase name, so fonts are only searched for (and warned about)
+/// once, and every spelling of a family shares the same name.
+fn lookup(family: &str) -> Option<&'static str> {
+	static CACHE: OnceLock<Mutex<HashMap<String, Option<&'static str>>>> = OnceLock::new();
+
+	let family = family.trim();
+	if family.is_empty() {
+		return None;
+	}
+
+	let mut cache = match CACHE.get_or_init(Mutex::default).lock() {
+		Ok(cache) => cache,
+		Err(e) => e.into_inner(),
+	};
+
+	let key = family.to_lowercase();
+	*cache
+		.entry(key.clone())
+		.or_insert_with(|| match installed().get(&key) {
+			// Cosmic requires font names to be static
+			Some(family) => Some(String::leak(family.name.clone())),
+			None => {
+				warn!("Font \"{family}\" is not installed, using fallback");
+				None
+			}
+		})
+}
+
+/// A font family installed on the system
+struct Installed {
+	name: String,
+	monospace: bool,
+}
+
+/// Every font family installed on the system, by its lowercase name
+fn installed() -> &'static HashMap<String, Installed> {
+	static INSTALLED: OnceLock<HashMap<String, Installed>> = OnceLock::new();
+
+	INSTALLED.get_or_init(|| {
+		let mut db = fontdb::Database::new();
+		db.load_system_fonts();
+
+		db.faces()
+			.flat_map(|face| {
+				face.families.iter().map(|(name, _)| {
+					let family = Installed {
+						name: name.clone(),
+						monospace: face.monospaced,
+					};
+					(name.to_lowercase(), family)
+				})
+			})
+			.collect()
+	})
+}
+
+/// Loads the bundled fonts, which are used in place of fonts that are not set or not installed
+pub fn load<M: Send + 'static>() -> Task<M> {
+	Task::batch(
+		Bundled::iter()
+			.filter(|file| file.ends_with(".ttf"))
+			.filter_map(|file| Bundled::get(&file))
+			.map(|file| font::load(file.data).discard()),
+	)
+}
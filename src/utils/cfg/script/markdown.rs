@@ -0,0 +1,13 @@
+use crate::{md, utils::ok_or_default};
+
+use super::fmt::FormatStyle;
+
+/// Rewraps every paragraph of the text to fit within the width
+pub fn reflow(text: String, width: i64) -> String {
+	md::reflow::reflow_all(&text, ok_or_default(usize::try_from(width)))
+}
+
+/// Normalizes the style of the text
+pub fn format(text: String, style: FormatStyle) -> String {
+	md::fmt::format(&text, &style.options())
+}
@@ -0,0 +1,113 @@
+use cosmic::iced::Color;
+use rhai::{Array, CustomType, Dynamic, TypeBuilder};
+use tracing::warn;
+
+/// How the elements of the preview are styled, as set in the config
+#[derive(Clone, CustomType)]
+pub struct PreviewStyle {
+	/// The size of headings from level 1 to 6, relative to the text size
+	pub heading_sizes: Array,
+	/// The color of headings from level 1 to 6
+	/// Levels that are left out or set to () use the color of the text
+	pub heading_colors: Array,
+	/// The space between paragraphs and other blocks, relative to the text size
+	pub paragraph_spacing: f32,
+	/// The background of code blocks
+	/// Left as () to use the background of cards
+	pub code_background: Dynamic,
+	/// The color of the border of block quotes, other than alerts
+	/// Left as () to use a faded text color
+	pub quote_border: Dynamic,
+	/// The width of the border of block quotes, in pixels
+	pub quote_border_width: f32,
+	/// The color of links
+	/// Left as () to use the primary color of the palette
+	pub link_color: Dynamic,
+	/// How far lists are indented from the text around them, relative to the text size
+	pub list_indent: f32,
+	/// The widest the content of the preview gets, in pixels
+	/// Unlimited if set to 0
+	pub max_width: f32,
+}
+
+impl PreviewStyle {
+	/// The size of headings from level 1 to 6, falling back to the defaults for levels that are
+	/// left out or not numbers
+	pub fn heading_sizes(&self) -> [f32; 6] {
+		let mut sizes = DEFAULT_HEADING_SIZES;
+
+		for (size, value) in sizes.iter_mut().zip(&self.heading_sizes) {
+			// Whole numbers are allowed as well
+			match value
+				.as_float()
+				.or_else(|_| value.as_int().map(|int| int as f32))
+			{
+				Ok(value) => *size = value.max(0.),
+				Err(e) => warn!("Heading size must be a number, not {e}"),
+			}
+		}
+
+		sizes
+	}
+
+	/// The color of headings from level 1 to 6, if set
+	pub fn heading_colors(&self) -> [Option<Color>; 6] {
+		let mut colors = [None; 6];
+
+		for (level, value) in colors.iter_mut().zip(&self.heading_colors) {
+			*level = color("Heading color", value);
+		}
+
+		colors
+	}
+
+	pub fn code_background(&self) -> Option<Color> {
+		color("Code block background", &self.code_background)
+	}
+
+	pub fn quote_border(&self) -> Option<Color> {
+		color("Block quote border", &self.quote_border)
+	}
+
+	pub fn link_color(&self) -> Option<Color> {
+		color("Link color", &self.link_color)
+	}
+
+	pub fn max_width(&self) -> Option<f32> {
+		(self.max_width > 0.).then_some(self.max_width)
+	}
+}
+
+impl Default for PreviewStyle {
+	fn default() -> Self {
+		Self {
+			heading_sizes: DEFAULT_HEADING_SIZES
+				.into_iter()
+				.map(Dynamic::from_float)
+				.collect(),
+			heading_colors: Array::new(),
+			paragraph_spacing: 1.,
+			code_background: Dynamic::UNIT,
+			quote_border: Dynamic::UNIT,
+			quote_border_width: 1.,
+			link_color: Dynamic::UNIT,
+			list_indent: 0.,
+			max_width: 0.,
+		}
+	}
+}
+
+const DEFAULT_HEADING_SIZES: [f32; 6] = [2., 1.75, 1.5, 1.25, 1., 1.];
+
+/// Reads a color that may be left unset as ()
+fn color(name: &str, value: &Dynamic) -> Option<Color> {
+	if value.is_unit() {
+		return None;
+	}
+
+	let color = value.clone().try_cast::<Color>();
+	if color.is_none() {
+		warn!("{name} must be a color, not {}", value.type_name());
+	}
+	color
+}
@@ -0,0 +1,93 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+	DefaultBytes, default_bytes, deserialize_or_default, get_or_create_cfg_file, serialize_or_log,
+};
+
+pub const DIR: &str = ".zoom";
+
+const MIN_SCALE: f32 = 0.5;
+const MAX_SCALE: f32 = 3.;
+
+/// The part of the editor being zoomed
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Pane {
+	Both,
+	Editor,
+	Preview,
+}
+
+/// How much the text of the editor and preview are scaled by
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Scale {
+	pub editor: f32,
+	pub preview: f32,
+}
+
+/// The zoom levels saved between sessions
+#[derive(Serialize, Deserialize, Default)]
+pub struct Zoom {
+	pub global: Scale,
+	/// Only used if "zoom_per_tab" is enabled
+	files: HashMap<PathBuf, Scale>,
+}
+
+impl Scale {
+	/// Adds the amount to the scale of the provided [Pane]
+	pub fn zoom(self, pane: Pane, amount: f32) -> Self {
+		let zoom = |scale: f32| (scale + amount).clamp(MIN_SCALE, MAX_SCALE);
+
+		match pane {
+			Pane::Both => Self {
+				editor: zoom(self.editor),
+				preview: zoom(self.preview),
+			},
+			Pane::Editor => Self {
+				editor: zoom(self.editor),
+				..self
+			},
+			Pane::Preview => Self {
+				preview: zoom(self.preview),
+				..self
+			},
+		}
+	}
+}
+
+impl Default for Scale {
+	fn default() -> Self {
+		Self {
+			editor: 1.,
+			preview: 1.,
+		}
+	}
+}
+
+impl Zoom {
+	pub fn read(path: PathBuf) -> Self {
+		deserialize_or_default(path)
+	}
+
+	pub fn write(&self) {
+		serialize_or_log(get_or_create_cfg_file::<_, Self>(DIR), self);
+	}
+
+	/// Gets the scale of a file, or the global scale if it has none
+	pub fn file(&self, path: Option<&PathBuf>) -> Scale {
+		path.and_then(|path| self.files.get(path))
+			.copied()
+			.unwrap_or(self.global)
+	}
+
+	pub fn set_file(&mut self, path: PathBuf, scale: Scale) {
+		self.files.insert(path, scale);
+	}
+}
+
+impl DefaultBytes for Zoom {
+	fn default_bytes() -> impl AsRef<[u8]> {
+		default_bytes::<Self>()
+	}
+}
@@ -18,6 +18,33 @@ pub fn args() -> ArgMatches {
 		)
 		// TODO: --default_config option
 		//.arg(arg!(-r --reset_config ... "Reset the config file").action(ArgAction::SetTrue))
+		.subcommand(
+			Command::new("fmt")
+				.about("Format markdown files using the style set in the config")
+				.arg(
+					arg!(--check "Only list the files that are not formatted, failing if there are any"),
+				)
+				.arg(arg!(<FILES> ... "The files to format").value_parser(value_parser!(PathBuf))),
+		)
+		.subcommand(
+			Command::new("lint")
+				.about("Check markdown files for problems using the rules set in the config")
+				.arg(arg!(<FILES> ... "The files to check").value_parser(value_parser!(PathBuf))),
+		)
+		.subcommand(
+			Command::new("check-links")
+				.about("Check that the links and images of markdown files lead somewhere")
+				.arg(arg!(--external "Also check that external URLs can be reached"))
+				.arg(
+					arg!([PATHS] ... "The files to check, or directories to check every file within")
+						.value_parser(value_parser!(PathBuf)),
+				),
+		)
+		.subcommand(
+			Command::new("export")
+				.about("Export markdown files as HTML, placing each next to the original")
+				.arg(arg!(<FILES> ... "The files to export").value_parser(value_parser!(PathBuf))),
+		)
 		.get_matches()
 }
 
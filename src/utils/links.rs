@@ -0,0 +1,201 @@
+//! Checks that the links and images of documents lead somewhere
+
+use std::{
+	collections::HashMap,
+	fs,
+	ops::Range,
+	path::{Path, PathBuf},
+	thread,
+	time::Duration,
+};
+
+use cosmic::widget::markdown::Url;
+
+use crate::{app::state::editor::preview::parse::resolve, md};
+
+/// How long an external URL is given to respond
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many external URLs are requested at once
+const MAX_REQUESTS: usize = 8;
+
+/// Why a link does not lead anywhere
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Reason {
+	MissingFile,
+	MissingImage,
+	/// The document exists, but has no heading with the anchor
+	MissingAnchor,
+	/// The external URL could not be requested, along with the error
+	Unreachable(String),
+}
+
+/// A link or image that does not lead anywhere
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BrokenLink {
+	/// The document the link is in, which is None if it has not been saved
+	pub path: Option<PathBuf>,
+	/// Starting from 0
+	pub line: usize,
+	/// The byte range of the link within the line
+	pub columns: Range<usize>,
+	pub url: String,
+	pub reason: Reason,
+}
+
+/// Checks the links of documents, remembering the documents and URLs it has already looked at
+pub struct Checker {
+	/// Whether external URLs are requested
+	external: bool,
+	/// Shared by every request, so that connections are reused
+	agent: ureq::Agent,
+	/// The text of every linked document that has been read
+	documents: HashMap<PathBuf, String>,
+	/// The error of every external URL that has been requested, if it could not be reached
+	urls: HashMap<String, Option<String>>,
+}
+
+impl Checker {
+	pub fn new(external: bool) -> Self {
+		Self {
+			external,
+			agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+			documents: HashMap::new(),
+			urls: HashMap::new(),
+		}
+	}
+
+	/// Checks every link and image of the document.
+	///
+	/// Relative links of documents that have not been saved are left out, since they lead nowhere
+	/// yet.
+	pub fn check(&mut self, path: Option<&Path>, text: &str) -> Vec<BrokenLink> {
+		let targets = md::links::targets(text);
+		self.request_all(&targets);
+
+		targets
+			.into_iter()
+			.filter_map(|target| {
+				let reason = self.reason(path, text, &target.url, target.image)?;
+				Some(BrokenLink {
+					path: path.map(Path::to_path_buf),
+					line: target.line,
+					columns: target.columns,
+					url: target.url,
+					reason,
+				})
+			})
+			.collect()
+	}
+
+	/// Why the link does not lead anywhere, or None if it does
+	fn reason(
+		&mut self,
+		path: Option<&Path>,
+		text: &str,
+		url: &str,
+		image: bool,
+	) -> Option<Reason> {
+		if let Some(anchor) = url.strip_prefix('#') {
+			return (!anchor.is_empty() && md::anchor::find(text, anchor).is_none())
+				.then_some(Reason::MissingAnchor);
+		}
+
+		if let Ok(parsed) = Url::parse(url) {
+			if parsed.scheme() != "file" {
+				return self
+					.is_requested(&parsed)
+					.then(|| self.request(url))
+					.flatten()
+					.map(Reason::Unreachable);
+			}
+		}
+
+		let target = resolve(url, path.and_then(Path::parent))?;
+		if !target.exists() {
+			return Some(if image {
+				Reason::MissingImage
+			} else {
+				Reason::MissingFile
+			});
+		}
+
+		let anchor = url
+			.split_once('#')
+			.map(|(_, anchor)| anchor)
+			.filter(|anchor| !anchor.is_empty())?;
+		if image || !md::is_markdown(&target) {
+			return None;
+		}
+
+		// The document may link to itself, and have changed since it was saved
+		let linked = if Some(target.as_path()) == path {
+			text
+		} else {
+			self.documents
+				.entry(target.clone())
+				.or_insert_with(|| fs::read_to_string(&target).unwrap_or_default())
+				.as_str()
+		};
+		md::anchor::find(linked, anchor)
+			.is_none()
+			.then_some(Reason::MissingAnchor)
+	}
+
+	/// Whether the external URL is requested, rather than assumed to be reachable
+	fn is_requested(&self, url: &Url) -> bool {
+		self.external && matches!(url.scheme(), "http" | "https")
+	}
+
+	/// Requests the external URLs of the links that have not been requested yet, several at a time
+	fn request_all(&mut self, targets: &[md::links::Target]) {
+		let mut urls = targets
+			.iter()
+			.map(|target| target.url.as_str())
+			.filter(|url| Url::parse(url).is_ok_and(|url| self.is_requested(&url)))
+			.filter(|url| !self.urls.contains_key(*url))
+			.collect::<Vec<&str>>();
+		urls.sort_unstable();
+		urls.dedup();
+
+		for urls in urls.chunks(MAX_REQUESTS) {
+			let agent = &self.agent;
+			let errors = thread::scope(|scope| {
+				let requests = urls
+					.iter()
+					.map(|url| scope.spawn(move || request(agent, url)))
+					.collect::<Vec<_>>();
+				requests
+					.into_iter()
+					.map(|handle| {
+						handle
+							.join()
+							.unwrap_or_else(|_| Some("The request panicked".into()))
+					})
+					.collect::<Vec<Option<String>>>()
+			});
+
+			self.urls
+				.extend(urls.iter().map(|url| url.to_string()).zip(errors));
+		}
+	}
+
+	/// Requests an external URL once, returning the error if it could not be reached
+	fn request(&mut self, url: &str) -> Option<String> {
+		self.urls
+			.entry(url.into())
+			.or_insert_with(|| request(&self.agent, url))
+			.clone()
+	}
+}
+
+/// Requests an external URL, returning the error if it could not be reached
+fn request(agent: &ureq::Agent, url: &str) -> Option<String> {
+	// Some servers only answer GET requests
+	match agent.head(url).call() {
+		Ok(_) => None,
+		Err(ureq::Error::Status(405, _)) => agent.get(url).call().err(),
+		Err(e) => Some(e),
+	}
+	.map(|e| e.to_string())
+}
@@ -0,0 +1,109 @@
+//! The notes of a workspace, which wiki links point to by name
+
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+
+use tracing::{error, info};
+
+use crate::md;
+
+/// Every Markdown document within the workspace and its subdirectories, leaving out hidden ones
+pub fn list(workspace: &Path) -> Vec<PathBuf> {
+	let mut notes = vec![];
+	let mut dirs = vec![workspace.to_path_buf()];
+
+	while let Some(dir) = dirs.pop() {
+		let Ok(entries) = fs::read_dir(&dir) else {
+			continue;
+		};
+
+		for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+			if path
+				.file_name()
+				.is_some_and(|name| name.to_string_lossy().starts_with('.'))
+			{
+				continue;
+			}
+
+			if path.is_dir() {
+				dirs.push(path);
+			} else if md::is_markdown(&path) {
+				notes.push(path);
+			}
+		}
+	}
+
+	notes.sort();
+	notes
+}
+
+/// The name a note is linked to by, which is its file name without the extension
+pub fn name(path: &Path) -> String {
+	path.file_stem()
+		.map(|stem| stem.to_string_lossy().to_string())
+		.unwrap_or_default()
+}
+
+/// The note a wiki link points to, either by its name or by its path within the workspace.
+///
+/// Names are compared case-insensitively, preferring the note closest to the root of the workspace.
+pub fn find(notes: &[PathBuf], workspace: &Path, note: &str) -> Option<PathBuf> {
+	notes
+		.iter()
+		.filter(|path| links_to(workspace, note, path))
+		.min_by_key(|path| path.components().count())
+		.cloned()
+}
+
+/// Checks if a wiki link points to the note
+pub fn links_to(workspace: &Path, note: &str, path: &Path) -> bool {
+	if note.contains('/') {
+		workspace.join(note) == path || workspace.join(format!("{note}.md")) == path
+	} else {
+		name(path).to_lowercase() == note.to_lowercase()
+	}
+}
+
+/// Creates a note for a wiki link that does not lead anywhere yet, titled with its name
+pub fn create(workspace: &Path, note: &str) -> Option<PathBuf> {
+	let path = workspace.join(format!("{note}.md"));
+	if path.exists() {
+		return Some(path);
+	}
+
+	if let Some(dir) = path.parent() {
+		if let Err(e) = fs::create_dir_all(dir) {
+			error!("Directory of note {note} could not be created: {e}");
+			return None;
+		}
+	}
+
+	let title = note.rsplit('/').next().unwrap_or(note);
+	match fs::write(&path, format!("# {title}\n")) {
+		Ok(()) => {
+			info!("Created note {path:?}");
+			Some(path)
+		}
+		Err(e) => {
+			error!("Note {note} could not be created: {e}");
+			None
+		}
+	}
+}
+
+/// Every note with a wiki link pointing to the document
+pub fn backlinks(notes: &[PathBuf], workspace: &Path, document: &Path) -> Vec<PathBuf> {
+	notes
+		.iter()
+		.filter(|path| *path != document)
+		.filter(|path| {
+			let text = fs::read_to_string(path).unwrap_or_default();
+			md::wiki::links(&text)
+				.iter()
+				.any(|link| !link.note.is_empty() && links_to(workspace, &link.note, document))
+		})
+		.cloned()
+		.collect()
+}
@@ -0,0 +1,167 @@
+//! Loads Hunspell dictionaries and the personal dictionary
+
+use std::{
+	collections::{HashMap, HashSet},
+	fs,
+	path::PathBuf,
+	sync::{Arc, Mutex, OnceLock, RwLock},
+};
+
+use spellbook::Dictionary;
+use tracing::{error, info, warn};
+
+use super::cfg::{DefaultBytes, get_or_create_cfg_dir, get_or_create_cfg_file};
+
+/// The file words added to the personal dictionary are stored in, one per line
+const PERSONAL: &str = "personal.dic";
+
+/// Where dictionaries are searched for, after the config and data dirs
+const SYSTEM_DIRS: [&str; 3] = [
+	"/usr/share/hunspell",
+	"/usr/share/myspell",
+	"/usr/share/myspell/dicts",
+];
+
+struct Personal;
+
+impl DefaultBytes for Personal {
+	fn default_bytes() -> impl AsRef<[u8]> {
+		""
+	}
+}
+
+/// Finds and loads the dictionary for a language, such as "en-US".
+///
+/// Results are cached, so dictionaries are only loaded (and warned about) once.
+pub fn dictionary(language: &str) -> Option<Arc<Dictionary>> {
+	static CACHE: OnceLock<Mutex<HashMap<String, Option<Arc<Dictionary>>>>> = OnceLock::new();
+
+	let mut cache = match CACHE.get_or_init(Mutex::default).lock() {
+		Ok(cache) => cache,
+		Err(e) => e.into_inner(),
+	};
+
+	cache
+		.entry(language.into())
+		.or_insert_with(|| load(language).map(Arc::new))
+		.clone()
+}
+
+/// Checks if the word is in the dictionary or the personal dictionary
+pub fn is_correct(dictionary: &Dictionary, word: &str) -> bool {
+	let personal = match personal().read() {
+		Ok(personal) => personal,
+		Err(e) => e.into_inner(),
+	};
+
+	personal.contains(word) || dictionary.check(word)
+}
+
+pub fn suggest(dictionary: &Dictionary, word: &str) -> Vec<String> {
+	let mut suggestions = vec![];
+	dictionary.suggest(word, &mut suggestions);
+	suggestions
+}
+
+/// Adds the word to the personal dictionary, so that it is never marked as misspelled
+pub fn add_to_personal(word: &str) {
+	let mut personal = match personal().write() {
+		Ok(personal) => personal,
+		Err(e) => e.into_inner(),
+	};
+
+	if !personal.insert(word.into()) {
+		return;
+	}
+
+	let mut words = personal.iter().cloned().collect::<Vec<String>>();
+	words.sort();
+
+	if let Err(e) = fs::write(
+		get_or_create_cfg_file::<_, Personal>(PERSONAL),
+		words.join("\n"),
+	) {
+		error!("Error when saving the personal dictionary: {e}");
+	}
+}
+
+fn personal() -> &'static RwLock<HashSet<String>> {
+	static PERSONAL_WORDS: OnceLock<RwLock<HashSet<String>>> = OnceLock::new();
+
+	PERSONAL_WORDS.get_or_init(|| {
+		let words = fs::read_to_string(get_or_create_cfg_file::<_, Personal>(PERSONAL))
+			.unwrap_or_default()
+			.lines()
+			.map(str::trim)
+			.filter(|word| !word.is_empty())
+			.map(String::from)
+			.collect();
+		RwLock::new(words)
+	})
+}
+
+fn load(language: &str) -> Option<Dictionary> {
+	let Some(path) = find(language) else {
+		warn!("No dictionary found for {language}, spell checking is disabled");
+		return None;
+	};
+
+	let aff = fs::read_to_string(path.with_extension("aff"));
+	let dic = fs::read_to_string(path.with_extension("dic"));
+
+	let (aff, dic) = match (aff, dic) {
+		(Ok(aff), Ok(dic)) => (aff, dic),
+		(Err(e), _) | (_, Err(e)) => {
+			error!("Dictionary {path:?} could not be read: {e}");
+			return None;
+		}
+	};
+
+	match Dictionary::new(&aff, &dic) {
+		Ok(dictionary) => {
+			info!("Loaded dictionary {path:?}");
+			Some(dictionary)
+		}
+		Err(e) => {
+			error!("Dictionary {path:?} could not be parsed: {e}");
+			None
+		}
+	}
+}
+
+/// Finds the dictionary for a language, returning its path without an extension.
+///
+/// Prefers an exact match, such as "en_US" for "en-US", falling back to any variant of the language.
+fn find(language: &str) -> Option<PathBuf> {
+	let name = language.replace('-', "_");
+	let base = name.split('_').next().unwrap_or_default().to_string();
+
+	let mut search = vec![get_or_create_cfg_dir().join("dictionaries")];
+	search.extend(dirs::data_dir().map(|dir| dir.join("hunspell")));
+	search.extend(SYSTEM_DIRS.into_iter().map(PathBuf::from));
+
+	let dictionaries = search
+		.iter()
+		.filter_map(|dir| fs::read_dir(dir).ok())
+		.flatten()
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| path.extension().is_some_and(|ext| ext == "dic"))
+		.collect::<Vec<PathBuf>>();
+
+	let stem = |path: &PathBuf| {
+		path.file_stem()
+			.map(|stem| stem.to_string_lossy().to_string())
+			.unwrap_or_default()
+	};
+
+	dictionaries
+		.iter()
+		.find(|path| stem(path) == name)
+		.or_else(|| dictionaries.iter().find(|path| stem(path) == base))
+		.or_else(|| {
+			dictionaries
+				.iter()
+				.find(|path| stem(path).starts_with(&format!("{base}_")))
+		})
+		.map(|path| path.with_extension(""))
+}
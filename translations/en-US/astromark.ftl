@@ -13,5 +13,105 @@ default_text = Type Here
 file = File
 save = Save
 save_as = Save As
+export_html = Export as HTML
 new_tab = New Tab
 go_home = Go Home
+
+edit = Edit
+undo = Undo
+redo = Redo
+add_cursor_above = Add Cursor Above
+add_cursor_below = Add Cursor Below
+add_next_occurrence = Add Next Occurrence
+select_all_occurrences = Select All Occurrences
+reflow = Reflow Paragraph
+surround = Surround With…
+surround_prompt = Surround with
+cancel = Cancel
+
+format = Format
+format_document = Format Document
+bold = Bold
+italic = Italic
+strikethrough = Strikethrough
+inline_code = Inline Code
+code_block = Code Block
+link = Link
+link_prompt = URL
+heading_up = Heading Level Up
+heading_down = Heading Level Down
+table_align = Align Table
+table_insert_row = Insert Row
+table_delete_row = Delete Row
+table_insert_column = Insert Column
+table_delete_column = Delete Column
+table_sort = Sort by Column
+
+view = View
+zoom_in = Zoom In
+zoom_out = Zoom Out
+zoom_reset = Reset Zoom
+zoom_in_editor = Zoom In Editor
+zoom_out_editor = Zoom Out Editor
+zoom_in_preview = Zoom In Preview
+zoom_out_preview = Zoom Out Preview
+problems = Show Problems
+
+no_problems = No problems found
+metadata = Show Metadata
+no_metadata = No front matter
+backlinks = Show Backlinks
+no_backlinks = No notes link here
+lint_heading_increment = Heading level skipped, expected a level { $level } heading
+lint_duplicate_heading = Duplicate heading "{ $heading }"
+lint_broken_reference = Reference "{ $reference }" is not defined
+lint_missing_alt = Image has no alt text
+lint_trailing_spaces = Trailing whitespace
+lint_list_marker = Inconsistent list marker, expected "{ $marker }"
+lint_bare_url = Bare URL { $url }, place it in a link
+check_links = Check Links
+check_workspace_links = Check Links in Workspace
+link_missing_file = Link to { $url } leads to a file that does not exist
+link_missing_image = Image { $url } does not exist
+link_missing_anchor = Link to { $url } leads to a heading that does not exist
+link_unreachable = Link to { $url } could not be reached: { $error }
+
+no_suggestions = No suggestions
+add_to_dictionary = Add to Dictionary
+no_notes = No notes found
+open_note = Open { $note }
+create_note = Create { $note }
+
+status_words = { $count ->
+    [one] 1 word
+   *[other] { $count } words
+}
+status_characters = { $count ->
+    [one] 1 character
+   *[other] { $count } characters
+}
+status_lines = { $count ->
+    [one] 1 line
+   *[other] { $count } lines
+}
+status_reading_time = { $minutes } min read
+status_selection = { $words } words, { $characters } characters selected
+status_cursor = Ln { $line }, Col { $column }
+
+image = Image
+image_loading = Loading { $alt }…
+image_missing = Image { $alt } not found at { $path }
+image_invalid = Image { $alt } could not be decoded
+image_too_large = Image { $alt } is too large to show
+image_unsupported = Image { $alt } is not a local file
+alert_note = Note
+alert_tip = Tip
+alert_important = Important
+alert_warning = Warning
+alert_caution = Caution
+diagram_rendering = Rendering { $language } diagram…
+diagram_failed = The { $language } diagram could not be rendered
+diagram_no_renderer = No command is set to render { $language } diagrams in "diagram_commands"
+diagram_unsupported = The diagram uses features that are not supported
+diagram_no_command = The command is empty
+diagram_timed_out = The diagram took longer than { $seconds } seconds to render